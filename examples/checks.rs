@@ -5,8 +5,6 @@ use hubcaps::git::GetReferenceResponse;
 use hubcaps::{Credentials, Github, InstallationTokenGenerator, JWTCredentials};
 use std::env;
 use std::error::Error;
-use std::fs::File;
-use std::io::Read;
 
 const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
@@ -19,9 +17,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let repo = env::var("GH_REPO")?;
     let branch = env::var("GH_BRANCH")?;
 
-    let mut key = Vec::new();
-    File::open(&key_file)?.read_to_end(&mut key)?;
-    let cred = JWTCredentials::new(app_id.parse().expect("Bad GH_APP_ID"), key)?;
+    let cred = JWTCredentials::from_file(app_id.parse().expect("Bad GH_APP_ID"), key_file)?;
 
     let mut github = Github::new(USER_AGENT, Credentials::JWT(cred.clone()))?;
     let installation = github
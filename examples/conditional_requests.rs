@@ -1,7 +1,5 @@
 #[cfg(feature = "httpcache")]
 use hubcaps::{Github, HttpCache};
-#[cfg(feature = "httpcache")]
-use reqwest::Client;
 use std::error::Error;
 
 #[tokio::main]
@@ -16,11 +14,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     #[cfg(feature = "httpcache")]
     {
-        let host = "https://api.github.com";
         let agent = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
-        let client = Client::builder().build()?;
-        let http_cache = HttpCache::in_home_dir();
-        let github = Github::custom(host, agent, None, client, http_cache);
+        let github = Github::builder(agent)
+            .http_cache(<dyn HttpCache>::in_home_dir())
+            .build()?;
 
         let _repos = github
             .user_repos("dwijnand")
@@ -1,5 +1,9 @@
-//! Labels interface
-use serde::Deserialize;
+//! GitHub Apps interface
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
 
 use self::super::{AuthenticationConstraint, Future, Github, MediaType};
 
@@ -26,6 +30,21 @@ impl App {
         )
     }
 
+    /// Create an installation access token restricted to a subset of the
+    /// installation's repositories and permissions
+    pub fn make_scoped_access_token(
+        &self,
+        installation_id: u64,
+        options: &AccessTokenOptions,
+    ) -> Future<AccessToken> {
+        self.github.post_media::<AccessToken>(
+            &self.path(&format!("/installations/{}/access_tokens", installation_id)),
+            json!(options),
+            MediaType::Preview("machine-man"),
+            AuthenticationConstraint::JWT,
+        )
+    }
+
     pub fn find_repo_installation<O, R>(&self, owner: O, repo: R) -> Future<Installation>
     where
         O: Into<String>,
@@ -38,27 +57,250 @@ impl App {
     }
 }
 
+/// Restricts the repositories and permissions of an installation access token
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct AccessTokenOptions {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    repositories: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    repository_ids: Vec<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permissions: Option<Permissions>,
+}
+
+impl AccessTokenOptions {
+    pub fn builder() -> AccessTokenOptionsBuilder {
+        AccessTokenOptionsBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct AccessTokenOptionsBuilder(AccessTokenOptions);
+
+impl AccessTokenOptionsBuilder {
+    /// names of the repositories the token may access
+    pub fn repositories<R>(&mut self, repositories: Vec<R>) -> &mut Self
+    where
+        R: Into<String>,
+    {
+        self.0.repositories = repositories.into_iter().map(|r| r.into()).collect();
+        self
+    }
+
+    /// ids of the repositories the token may access
+    pub fn repository_ids(&mut self, repository_ids: Vec<u64>) -> &mut Self {
+        self.0.repository_ids = repository_ids;
+        self
+    }
+
+    /// permissions of the token, which may not exceed the installation's
+    pub fn permissions(&mut self, permissions: Permissions) -> &mut Self {
+        self.0.permissions = Some(permissions);
+        self
+    }
+
+    pub fn build(&self) -> AccessTokenOptions {
+        self.0.clone()
+    }
+}
+
 // representations
 
 #[derive(Debug, Deserialize)]
 pub struct AccessToken {
     pub token: String,
     pub expires_at: String,
+    pub permissions: Option<Permissions>,
+    pub repository_selection: Option<String>,
+}
+
+impl AccessToken {
+    /// When the token expires, if `expires_at` is a valid timestamp
+    pub fn expires(&self) -> Option<SystemTime> {
+        parse_timestamp(&self.expires_at)
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Installation {
     pub id: u64,
-    // account: Account
+    pub account: Option<Account>,
     pub access_tokens_url: String,
     pub repositories_url: String,
     pub html_url: String,
     pub app_id: i32,
     pub target_id: i32,
     pub target_type: String,
-    // permissions: Permissions
+    #[serde(default)]
+    pub permissions: Permissions,
     pub events: Vec<String>,
-    // created_at, updated_at
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
     pub single_file_name: Option<String>,
     pub repository_selection: String,
 }
+
+/// The user or organization an app is installed on
+#[derive(Debug, Deserialize)]
+pub struct Account {
+    pub login: String,
+    pub id: u64,
+    pub avatar_url: Option<String>,
+    pub html_url: Option<String>,
+    #[serde(rename = "type")]
+    pub account_type: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionLevel {
+    Read,
+    Write,
+    Admin,
+}
+
+/// The permissions granted to an installation or requested for an access
+/// token. Permissions without a field of their own are kept in `other`
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Permissions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actions: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub administration: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checks: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contents: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployments: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issues: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub members: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packages: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_requests: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statuses: Option<PermissionLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflows: Option<PermissionLevel>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, PermissionLevel>,
+}
+
+/// Parse an RFC 3339 timestamp, such as `2016-07-11T22:14:10Z`
+fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(SystemTime::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(
+            parse_timestamp("2016-07-11T22:14:10Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_468_275_250))
+        );
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(UNIX_EPOCH));
+        assert_eq!(
+            parse_timestamp("2016-07-12T00:14:10+02:00"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_468_275_250))
+        );
+        assert_eq!(
+            parse_timestamp("2016-07-11T22:14:10.5Z"),
+            Some(UNIX_EPOCH + Duration::from_millis(1_468_275_250_500))
+        );
+        for malformed in &[
+            "2016-07-11",
+            "2016-02-30T00:00:00Z",
+            "2016-07-11T25:00:00Z",
+            "2016-07-11T22:14:10",
+            "2016-07-11T22:14:10Zjunk",
+            "-2016-07-11T22:14:10Z",
+        ] {
+            assert_eq!(parse_timestamp(malformed), None, "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn serializes_access_token_options() {
+        let options = AccessTokenOptions::builder()
+            .repositories(vec!["hubcaps"])
+            .permissions(Permissions {
+                contents: Some(PermissionLevel::Read),
+                other: vec![("secrets".to_string(), PermissionLevel::Write)]
+                    .into_iter()
+                    .collect(),
+                ..Permissions::default()
+            })
+            .build();
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            serde_json::json!({
+                "repositories": ["hubcaps"],
+                "permissions": {"contents": "read", "secrets": "write"}
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(AccessTokenOptions::default()).unwrap(),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn deserialize_installation() {
+        let installation: Installation = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "account": {"login": "octocat", "id": 1, "type": "User"},
+            "access_tokens_url": "https://api.github.com/app/installations/1/access_tokens",
+            "repositories_url": "https://api.github.com/installation/repositories",
+            "html_url": "https://github.com/settings/installations/1",
+            "app_id": 1,
+            "target_id": 1,
+            "target_type": "User",
+            "permissions": {"checks": "write", "metadata": "read", "secrets": "read"},
+            "events": ["push"],
+            "created_at": "2018-02-09T20:51:14Z",
+            "updated_at": "2018-02-09T20:51:14Z",
+            "single_file_name": null,
+            "repository_selection": "selected"
+        }))
+        .unwrap();
+        assert_eq!(installation.account.unwrap().login, "octocat");
+        assert_eq!(
+            installation.permissions.checks,
+            Some(PermissionLevel::Write)
+        );
+        assert_eq!(
+            installation.permissions.other.get("secrets"),
+            Some(&PermissionLevel::Read)
+        );
+
+        // installations without an account or permissions still deserialize
+        let installation: Installation = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "access_tokens_url": "https://api.github.com/app/installations/1/access_tokens",
+            "repositories_url": "https://api.github.com/installation/repositories",
+            "html_url": "https://github.com/settings/installations/1",
+            "app_id": 1,
+            "target_id": 1,
+            "target_type": "User",
+            "events": [],
+            "single_file_name": null,
+            "repository_selection": "all"
+        }))
+        .unwrap();
+        assert!(installation.account.is_none());
+        assert_eq!(installation.permissions, Permissions::default());
+    }
+}
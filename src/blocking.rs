@@ -0,0 +1,288 @@
+//! A blocking client
+//!
+//! For synchronous programs, such as command line tools and build scripts,
+//! `blocking::Github` runs hubcaps' async operations to completion on a
+//! runtime of its own. Its entry points mirror those of `hubcaps::Github`,
+//! wrapping each service in a `Blocking` which resolves the service's futures
+//! to `Result`s and turns its streams into iterators.
+//!
+//! This module requires the `blocking` cargo feature. A blocking client must
+//! not be used from within an async runtime.
+//!
+//! ```no_run
+//! use hubcaps::blocking::Github;
+//! use hubcaps::issues::IssueListOptions;
+//! use hubcaps::Credentials;
+//!
+//! # fn run() -> hubcaps::Result<()> {
+//! let github = Github::new(
+//!     "user-agent-name",
+//!     Credentials::Token("personal-access-token".into()),
+//! )?;
+//! let repo = github.repo("softprops", "hubcaps");
+//! let details = repo.run(|repo| repo.get())?;
+//! println!("{} stars", details.stargazers_count);
+//!
+//! let options = IssueListOptions::builder().build();
+//! for issue in repo.iter(|repo| repo.issues().iter(&options)) {
+//!     let issue = issue?;
+//!     println!("#{} {}", issue.number, issue.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::future::Future as StdFuture;
+use std::sync::Arc;
+
+use futures::prelude::*;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::activity::Activity;
+use crate::app::App;
+use crate::gists::{Gists, UserGists};
+use crate::graphql::GraphQL;
+use crate::organizations::{Organization, Organizations, UserOrganizations};
+use crate::rate_limit::RateLimit;
+use crate::raw::Raw;
+use crate::repositories::{OrganizationRepositories, Repositories, Repository, UserRepositories};
+use crate::search::Search;
+use crate::users::Users;
+use crate::{Credentials, Future, Result, Stream};
+
+/// Entry point interface for interacting with GitHub API synchronously.
+/// Clones share the same runtime
+#[derive(Clone)]
+pub struct Github {
+    github: crate::Github,
+    runtime: Arc<Runtime>,
+}
+
+impl Github {
+    pub fn new<A, C>(agent: A, credentials: C) -> Result<Self>
+    where
+        A: Into<String>,
+        C: Into<Option<Credentials>>,
+    {
+        Self::from_async(crate::Github::new(agent, credentials)?)
+    }
+
+    pub fn host<H, A, C>(host: H, agent: A, credentials: C) -> Result<Self>
+    where
+        H: Into<String>,
+        A: Into<String>,
+        C: Into<Option<Credentials>>,
+    {
+        Self::from_async(crate::Github::host(host, agent, credentials)?)
+    }
+
+    /// Block on the operations of an async client, e.g. one configured with
+    /// a custom transport, retry policy or middleware
+    pub fn from_async(github: crate::Github) -> Result<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(Github {
+            github,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// The async client this client blocks on
+    pub fn as_async(&self) -> &crate::Github {
+        &self.github
+    }
+
+    /// Run any future to completion, e.g. one of a `raw` request
+    pub fn block_on<F, T>(&self, future: F) -> Result<T>
+    where
+        F: StdFuture<Output = Result<T>>,
+    {
+        self.runtime.block_on(future)
+    }
+
+    /// Iterate over the items of any stream
+    pub fn iter<T>(&self, stream: Stream<T>) -> Iter<T> {
+        Iter {
+            stream,
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn rate_limit(&self) -> Blocking<RateLimit> {
+        self.wrap(self.github.rate_limit())
+    }
+
+    /// Return a reference to user activity
+    pub fn activity(&self) -> Blocking<Activity> {
+        self.wrap(self.github.activity())
+    }
+
+    /// Return a reference to a Github repository
+    pub fn repo<O, R>(&self, owner: O, repo: R) -> Blocking<Repository>
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        self.wrap(self.github.repo(owner, repo))
+    }
+
+    /// Return a reference to the collection of repositories owned by and
+    /// associated with an owner
+    pub fn user_repos<S>(&self, owner: S) -> Blocking<UserRepositories>
+    where
+        S: Into<String>,
+    {
+        self.wrap(self.github.user_repos(owner))
+    }
+
+    /// Return a reference to the collection of repositories owned by the user
+    /// associated with the current authentication credentials
+    pub fn repos(&self) -> Blocking<Repositories> {
+        self.wrap(self.github.repos())
+    }
+
+    pub fn org<O>(&self, org: O) -> Blocking<Organization>
+    where
+        O: Into<String>,
+    {
+        self.wrap(self.github.org(org))
+    }
+
+    /// Return a reference to the collection of organizations that the user
+    /// associated with the current authentication credentials is in
+    pub fn orgs(&self) -> Blocking<Organizations> {
+        self.wrap(self.github.orgs())
+    }
+
+    /// Return a reference to an interface that provides access
+    /// to user information.
+    pub fn users(&self) -> Blocking<Users> {
+        self.wrap(self.github.users())
+    }
+
+    /// Return a reference to the collection of organizations a user
+    /// is publicly associated with
+    pub fn user_orgs<U>(&self, user: U) -> Blocking<UserOrganizations>
+    where
+        U: Into<String>,
+    {
+        self.wrap(self.github.user_orgs(user))
+    }
+
+    /// Return a reference to an interface that provides access to a user's gists
+    pub fn user_gists<O>(&self, owner: O) -> Blocking<UserGists>
+    where
+        O: Into<String>,
+    {
+        self.wrap(self.github.user_gists(owner))
+    }
+
+    /// Return a reference to an interface that provides access to the
+    /// gists belonging to the owner of the token used to configure this client
+    pub fn gists(&self) -> Blocking<Gists> {
+        self.wrap(self.github.gists())
+    }
+
+    /// Return a reference to an interface that provides access to search operations
+    pub fn search(&self) -> Blocking<Search> {
+        self.wrap(self.github.search())
+    }
+
+    /// Return a reference to the collection of repositories owned by and
+    /// associated with an organization
+    pub fn org_repos<O>(&self, org: O) -> Blocking<OrganizationRepositories>
+    where
+        O: Into<String>,
+    {
+        self.wrap(self.github.org_repos(org))
+    }
+
+    /// Return a reference to raw requests, for endpoints without a dedicated
+    /// interface
+    pub fn raw(&self) -> Blocking<Raw> {
+        self.wrap(self.github.raw())
+    }
+
+    /// Return a reference to GitHub's GraphQL api
+    pub fn graphql(&self) -> Blocking<GraphQL> {
+        self.wrap(self.github.graphql())
+    }
+
+    /// Return a reference to GitHub Apps
+    pub fn app(&self) -> Blocking<App> {
+        self.wrap(self.github.app())
+    }
+
+    fn wrap<S>(&self, service: S) -> Blocking<S> {
+        Blocking {
+            service,
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for Github {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Github")
+            .field("github", &self.github)
+            .finish()
+    }
+}
+
+/// An async service whose operations are run to completion
+pub struct Blocking<S> {
+    service: S,
+    runtime: Arc<Runtime>,
+}
+
+impl<S> Blocking<S> {
+    /// Run one of the service's operations, e.g. `repo.run(|repo| repo.get())`
+    pub fn run<F, T>(&self, operation: F) -> Result<T>
+    where
+        F: FnOnce(&S) -> Future<T>,
+    {
+        self.runtime.block_on(operation(&self.service))
+    }
+
+    /// Iterate over the items of one of the service's listings, e.g.
+    /// `repo.iter(|repo| repo.labels().iter())`
+    pub fn iter<F, T>(&self, listing: F) -> Iter<T>
+    where
+        F: FnOnce(&S) -> Stream<T>,
+    {
+        Iter {
+            stream: listing(&self.service),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// One of the service's nested services, e.g. `repo.nested(|repo| repo.issues())`
+    pub fn nested<F, N>(&self, service: F) -> Blocking<N>
+    where
+        F: FnOnce(&S) -> N,
+    {
+        Blocking {
+            service: service(&self.service),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// The async service this wraps
+    pub fn as_async(&self) -> &S {
+        &self.service
+    }
+}
+
+/// An iterator over the items of a listing, fetching further pages as needed
+pub struct Iter<T> {
+    stream: Stream<T>,
+    runtime: Arc<Runtime>,
+}
+
+impl<T> Iterator for Iter<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        let stream = &mut self.stream;
+        self.runtime.block_on(stream.next())
+    }
+}
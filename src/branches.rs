@@ -4,6 +4,7 @@
 //! [Github docs](https://developer.github.com/v3/repos/branches/)
 use serde::{Deserialize, Serialize};
 
+use crate::pagination::Page;
 use crate::{Future, Github, MediaType, Stream};
 
 /// reference to gists associated with a github user
@@ -45,6 +46,25 @@ impl Branches {
         ))
     }
 
+    /// provides a stream over each page of branches for this repo
+    pub fn pages(&self) -> Stream<Page<Branch>> {
+        self.github.get_page_stream(&format!(
+            "/repos/{owner}/{repo}/branches",
+            owner = self.owner,
+            repo = self.repo
+        ))
+    }
+
+    /// fetch a single page of branches for this repo, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<Branch>> {
+        let uri = format!(
+            "/repos/{owner}/{repo}/branches",
+            owner = self.owner,
+            repo = self.repo
+        );
+        self.github.get_page(&uri, number)
+    }
+
     /// gets a branch for this repo by name
     pub fn get<B>(&self, branch: B) -> Future<Branch>
     where
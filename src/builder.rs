@@ -0,0 +1,432 @@
+//! Client construction
+//!
+//! `Github::new` and `Github::host` cover the common cases. Everything else,
+//! from timeouts and proxies to GitHub Enterprise Server hosts and
+//! middleware, is configured with a `GithubBuilder`.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use hubcaps::retry::RetryPolicy;
+//! use hubcaps::{Credentials, Github};
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let github = Github::builder("user-agent-name")
+//!     .enterprise("https://github.example.com")
+//!     .credentials(Credentials::Token("personal-access-token".into()))
+//!     .timeout(Duration::from_secs(30))
+//!     .proxy(reqwest::Proxy::https("http://proxy.example.com:8080")?)
+//!     .api_version("2022-11-28")
+//!     .per_page(100)
+//!     .retry_policy(RetryPolicy::builder().build())
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client;
+
+#[cfg(feature = "httpcache")]
+use crate::http_cache::{BoxedHttpCache, CacheMode, HttpCacheMiddleware};
+use crate::middleware::{BoxedMiddleware, Middleware};
+use crate::rate_limit::Throttle;
+use crate::retry::RetryPolicy;
+use crate::transport::{BoxedTransport, HttpTransport};
+use crate::{Credentials, Error, Github, Result, DEFAULT_HOST};
+
+const DEFAULT_UPLOADS_HOST: &str = "https://uploads.github.com";
+const X_GITHUB_API_VERSION: &str = "x-github-api-version";
+
+/// Configures and creates a `Github` client
+pub struct GithubBuilder {
+    agent: String,
+    host: String,
+    uploads_host: Option<String>,
+    graphql_url: Option<String>,
+    credentials: Option<Credentials>,
+    transport: Option<BoxedTransport>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxies: Vec<reqwest::Proxy>,
+    #[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+    root_certificates: Vec<reqwest::Certificate>,
+    default_headers: HeaderMap,
+    per_page: Option<u32>,
+    retry: RetryPolicy,
+    throttle: Option<Throttle>,
+    page_concurrency: usize,
+    middleware: Vec<BoxedMiddleware>,
+    #[cfg(feature = "httpcache")]
+    http_cache: Option<BoxedHttpCache>,
+    #[cfg(feature = "httpcache")]
+    cache_mode: CacheMode,
+    error: Option<Error>,
+}
+
+impl GithubBuilder {
+    pub(crate) fn new(agent: String) -> Self {
+        GithubBuilder {
+            agent,
+            host: DEFAULT_HOST.into(),
+            uploads_host: None,
+            graphql_url: None,
+            credentials: None,
+            transport: None,
+            timeout: None,
+            connect_timeout: None,
+            proxies: Vec::new(),
+            #[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+            root_certificates: Vec::new(),
+            default_headers: HeaderMap::new(),
+            per_page: None,
+            retry: RetryPolicy::none(),
+            throttle: None,
+            page_concurrency: 1,
+            middleware: Vec::new(),
+            #[cfg(feature = "httpcache")]
+            http_cache: None,
+            #[cfg(feature = "httpcache")]
+            cache_mode: CacheMode::default(),
+            error: None,
+        }
+    }
+
+    /// The root of the REST api. Defaults to `https://api.github.com`
+    pub fn host<H>(mut self, host: H) -> Self
+    where
+        H: Into<String>,
+    {
+        self.host = host.into();
+        self
+    }
+
+    /// Talk to a GitHub Enterprise Server instance, e.g.
+    /// `https://github.example.com`, using its `/api/v3`, `/api/uploads` and
+    /// `/api/graphql` paths
+    pub fn enterprise<U>(self, url: U) -> Self
+    where
+        U: Into<String>,
+    {
+        let url = url.into();
+        let url = url.trim_end_matches('/');
+        self.host(format!("{}/api/v3", url))
+            .uploads_host(format!("{}/api/uploads", url))
+            .graphql_url(format!("{}/api/graphql", url))
+    }
+
+    /// The root of the api release assets are uploaded to. Defaults to
+    /// `https://uploads.github.com` for github.com
+    pub fn uploads_host<H>(mut self, host: H) -> Self
+    where
+        H: Into<String>,
+    {
+        self.uploads_host = Some(host.into());
+        self
+    }
+
+    /// The GraphQL endpoint. Defaults to `/graphql` on github.com and
+    /// `/api/graphql` on GitHub Enterprise Server
+    pub fn graphql_url<U>(mut self, url: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.graphql_url = Some(url.into());
+        self
+    }
+
+    pub fn credentials<C>(mut self, credentials: C) -> Self
+    where
+        C: Into<Option<Credentials>>,
+    {
+        self.credentials = credentials.into();
+        self
+    }
+
+    /// Send requests through a custom `HttpTransport` rather than a
+    /// `reqwest::Client`. Timeouts, proxies and root certificates only apply
+    /// to the default transport
+    pub fn transport<T>(mut self, transport: T) -> Self
+    where
+        T: HttpTransport + 'static,
+    {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// How long to wait for a response, including its body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How long to wait for a connection to be established
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests through an HTTP(S) proxy
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Trust an additional root certificate, e.g. that of a GitHub Enterprise
+    /// Server instance with a self-signed certificate
+    #[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Send a header with every request, unless the request sets it itself
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Request a version of the REST api with the `X-GitHub-Api-Version`
+    /// header, e.g. `2022-11-28`
+    pub fn api_version(self, version: &str) -> Self {
+        match HeaderValue::from_str(version) {
+            Ok(value) => self.default_header(HeaderName::from_static(X_GITHUB_API_VERSION), value),
+            Err(err) => self.fail(http::Error::from(err).into()),
+        }
+    }
+
+    /// The number of items to request per page of a listing, streamed with
+    /// `iter` or fetched with `pages` and `page`, when its options don't say
+    /// otherwise. GitHub defaults to 30 and allows up to 100
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    /// See `Github::set_retry_policy`
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// See `Github::set_throttle`
+    pub fn throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// See `Github::set_page_concurrency`
+    pub fn page_concurrency(mut self, limit: usize) -> Self {
+        self.page_concurrency = limit.max(1);
+        self
+    }
+
+    /// Append a middleware to the chain every request passes through
+    pub fn middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Cache responses, revalidating them with conditional requests. The
+    /// cache sees requests before any other middleware
+    #[cfg(feature = "httpcache")]
+    pub fn http_cache(mut self, http_cache: BoxedHttpCache) -> Self {
+        self.http_cache = Some(http_cache);
+        self
+    }
+
+    /// See `Github::set_cache_mode`
+    #[cfg(feature = "httpcache")]
+    pub fn cache_mode(mut self, mode: CacheMode) -> Self {
+        self.cache_mode = mode;
+        self
+    }
+
+    pub fn build(mut self) -> Result<Github> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        let transport = match self.transport.take() {
+            Some(transport) => transport,
+            None => Arc::new(self.client()?),
+        };
+        Ok(self.finish(transport))
+    }
+
+    /// The default transport
+    fn client(&mut self) -> Result<Client> {
+        let mut client = Client::builder();
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        for proxy in self.proxies.drain(..) {
+            client = client.proxy(proxy);
+        }
+        #[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+        for certificate in self.root_certificates.drain(..) {
+            client = client.add_root_certificate(certificate);
+        }
+        Ok(client.build()?)
+    }
+
+    pub(crate) fn finish(self, transport: BoxedTransport) -> Github {
+        let mut middleware = Vec::new();
+        #[cfg(feature = "httpcache")]
+        {
+            let http_cache = self.http_cache.unwrap_or_else(<dyn crate::HttpCache>::noop);
+            middleware.push(Arc::new(HttpCacheMiddleware::new(http_cache)) as BoxedMiddleware);
+        }
+        middleware.extend(self.middleware);
+        let host = self.host;
+        let uploads_host = self
+            .uploads_host
+            .unwrap_or_else(|| default_uploads_host(&host));
+        let graphql_url = self
+            .graphql_url
+            .unwrap_or_else(|| default_graphql_url(&host));
+        Github {
+            host,
+            uploads_host,
+            graphql_url,
+            agent: self.agent,
+            transport,
+            credentials: self.credentials,
+            default_headers: Arc::new(self.default_headers),
+            per_page: self.per_page,
+            retry: self.retry,
+            throttle: self.throttle,
+            page_concurrency: self.page_concurrency,
+            rate_limits: Arc::default(),
+            middleware: Arc::new(middleware),
+            #[cfg(feature = "httpcache")]
+            cache_mode: self.cache_mode,
+            response_meta: None,
+        }
+    }
+
+    fn fail(mut self, err: Error) -> Self {
+        self.error.get_or_insert(err);
+        self
+    }
+}
+
+/// `/api/uploads` for GitHub Enterprise hosts
+fn default_uploads_host(host: &str) -> String {
+    let host = host.trim_end_matches('/');
+    if host == DEFAULT_HOST {
+        return DEFAULT_UPLOADS_HOST.into();
+    }
+    match host.strip_suffix("/api/v3") {
+        Some(enterprise) => format!("{}/api/uploads", enterprise),
+        None => host.into(),
+    }
+}
+
+/// `/api/graphql` for GitHub Enterprise hosts
+fn default_graphql_url(host: &str) -> String {
+    let host = host.trim_end_matches('/');
+    match host.strip_suffix("/api/v3") {
+        Some(enterprise) => format!("{}/api/graphql", enterprise),
+        None => format!("{}/graphql", host),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use futures::TryStreamExt;
+    use http::header::ACCEPT;
+    use http::StatusCode;
+
+    #[test]
+    fn enterprise_urls() {
+        let github = Github::builder("test-agent").build().unwrap();
+        assert_eq!(github.host, "https://api.github.com");
+        assert_eq!(github.uploads_host(), "https://uploads.github.com");
+        assert_eq!(github.graphql_url, "https://api.github.com/graphql");
+
+        let github = Github::builder("test-agent")
+            .enterprise("https://github.example.com/")
+            .build()
+            .unwrap();
+        assert_eq!(github.host, "https://github.example.com/api/v3");
+        assert_eq!(
+            github.uploads_host(),
+            "https://github.example.com/api/uploads"
+        );
+        assert_eq!(github.graphql_url, "https://github.example.com/api/graphql");
+
+        let github = Github::host("https://github.example.com/api/v3", "test-agent", None).unwrap();
+        assert_eq!(
+            github.uploads_host(),
+            "https://github.example.com/api/uploads"
+        );
+        assert_eq!(github.graphql_url, "https://github.example.com/api/graphql");
+    }
+
+    #[test]
+    fn invalid_api_version() {
+        assert!(Github::builder("test-agent")
+            .api_version("2022-11-28\n")
+            .build()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn builder_defaults() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, "[]"),
+            (StatusCode::OK, "[]"),
+            (StatusCode::OK, "{}"),
+        ]);
+        let github = transport
+            .builder()
+            .api_version("2022-11-28")
+            .default_header(ACCEPT, HeaderValue::from_static("text/plain"))
+            .per_page(100)
+            .build()
+            .unwrap();
+
+        github
+            .get_stream::<u32>("/user/repos")
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        github
+            .get_stream::<u32>("/user/repos?per_page=5")
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        github.get::<serde_json::Value>("/repos/o/r").await.unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(
+            requests[0].uri(),
+            "https://api.github.com/user/repos?per_page=100"
+        );
+        assert_eq!(
+            requests[1].uri(),
+            "https://api.github.com/user/repos?per_page=5"
+        );
+        assert_eq!(
+            requests[2].uri(),
+            "https://api.github.com/repos/o/r",
+            "only listings ask for a number of items per page"
+        );
+        assert_eq!(requests[0].headers()["x-github-api-version"], "2022-11-28");
+        assert_eq!(
+            requests[0].headers()[ACCEPT],
+            "application/vnd.github.v3+json",
+            "requests' own headers take precedence"
+        );
+    }
+}
@@ -0,0 +1,795 @@
+//! Record and replay client interactions
+//!
+//! A `Cassette` is an `HttpTransport` which either records the requests a
+//! `Github` client makes, along with the responses they received, to a JSON
+//! file, or replays a previously recorded file without touching the network.
+//! Recorded interactions are written to the file by `Cassette::finish`, or
+//! once the cassette is dropped.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use hubcaps::cassette::{Cassette, CassetteOptions};
+//! use hubcaps::{Credentials, Github};
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let options = CassetteOptions::default();
+//! // record once against the live api
+//! let cassette = Arc::new(Cassette::record(
+//!     "tests/cassettes/user.json",
+//!     reqwest::Client::new(),
+//!     &options,
+//! ));
+//! let github = Github::builder("my-cool-user-agent/0.1.0")
+//!     .credentials(Credentials::Token("personal-access-token".into()))
+//!     .transport(cassette.clone())
+//!     .build()?;
+//! github.users().authenticated().await?;
+//! cassette.finish().await?;
+//!
+//! // ...then replay in ci
+//! let cassette = Cassette::replay("tests/cassettes/user.json", &options)?;
+//! let github = Github::builder("my-cool-user-agent/0.1.0")
+//!     .credentials(Credentials::Token("personal-access-token".into()))
+//!     .transport(cassette)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use futures::future;
+use http::header::{HeaderName, ACCEPT, AUTHORIZATION};
+use http::{Request, Response, StatusCode, Uri};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::transport::{BoxedTransport, HttpTransport};
+use crate::{Error, ErrorKind, Future, Result};
+
+/// Replaces redacted values in recorded cassettes
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Whether a `Cassette` is recording or replaying
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Record,
+    Replay,
+}
+
+/// Options controlling how requests are matched and recorded
+#[derive(Clone, Debug)]
+pub struct CassetteOptions {
+    match_body: bool,
+    match_headers: Vec<HeaderName>,
+    ignore_query_params: Vec<String>,
+    redact_headers: Vec<HeaderName>,
+    redact_query_params: Vec<String>,
+    redact_values: Vec<String>,
+    strict: bool,
+}
+
+impl Default for CassetteOptions {
+    fn default() -> Self {
+        CassetteOptions {
+            match_body: true,
+            match_headers: vec![ACCEPT],
+            ignore_query_params: Vec::new(),
+            redact_headers: vec![AUTHORIZATION],
+            redact_query_params: vec!["client_id".into(), "client_secret".into()],
+            redact_values: Vec::new(),
+            strict: true,
+        }
+    }
+}
+
+impl CassetteOptions {
+    pub fn builder() -> CassetteOptionsBuilder {
+        CassetteOptionsBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct CassetteOptionsBuilder(CassetteOptions);
+
+impl CassetteOptionsBuilder {
+    /// compare request bodies when matching. JSON bodies are compared by value.
+    /// defaults to true
+    pub fn match_body(&mut self, match_body: bool) -> &mut Self {
+        self.0.match_body = match_body;
+        self
+    }
+
+    /// request headers which must be equal for a recording to match. Headers
+    /// not listed here, such as `Authorization`, are ignored. defaults to
+    /// `Accept`, which carries the requested media type
+    pub fn match_headers<H>(&mut self, headers: Vec<H>) -> &mut Self
+    where
+        H: AsRef<str>,
+    {
+        self.0.match_headers = header_names(headers);
+        self
+    }
+
+    /// query parameters to ignore when matching, e.g. timestamps. The order of
+    /// query parameters is never significant
+    pub fn ignore_query_params<P>(&mut self, params: Vec<P>) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        self.0.ignore_query_params = params.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// request and response headers whose values are replaced with `REDACTED`
+    /// when recording. defaults to `Authorization`
+    pub fn redact_headers<H>(&mut self, headers: Vec<H>) -> &mut Self
+    where
+        H: AsRef<str>,
+    {
+        self.0.redact_headers = header_names(headers);
+        self
+    }
+
+    /// query parameters whose values are replaced with `REDACTED` when
+    /// recording and in errors. defaults to `client_id` and `client_secret`,
+    /// which carry `Credentials::Client`
+    pub fn redact_query_params<P>(&mut self, params: Vec<P>) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        self.0.redact_query_params = params.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// a secret, such as a token, to replace with `REDACTED` wherever it
+    /// appears in a recording
+    pub fn redact_value<V>(&mut self, value: V) -> &mut Self
+    where
+        V: Into<String>,
+    {
+        let value = value.into();
+        if !value.is_empty() {
+            self.0.redact_values.push(value);
+        }
+        self
+    }
+
+    /// when replaying, fail requests with no matching recording with
+    /// `ErrorKind::UnmatchedRequest`. Otherwise they are answered with a
+    /// `404 Not Found`. defaults to true
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.0.strict = strict;
+        self
+    }
+
+    pub fn build(&self) -> CassetteOptions {
+        self.0.clone()
+    }
+}
+
+fn header_names<H>(headers: Vec<H>) -> Vec<HeaderName>
+where
+    H: AsRef<str>,
+{
+    headers
+        .iter()
+        .filter_map(|header| HeaderName::from_bytes(header.as_ref().as_bytes()).ok())
+        .collect()
+}
+
+/// An `HttpTransport` which records interactions to, or replays them from, a file
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    options: CassetteOptions,
+    transport: Option<BoxedTransport>,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    /// number of times each interaction has been replayed
+    plays: Vec<usize>,
+    /// whether interactions were recorded since the file was last written
+    unsaved: bool,
+}
+
+impl Cassette {
+    /// Send requests through `transport`, recording each interaction to the
+    /// file at `path` once the cassette is finished or dropped. Any existing
+    /// recording there is replaced
+    pub fn record<P, T>(path: P, transport: T, options: &CassetteOptions) -> Self
+    where
+        P: Into<PathBuf>,
+        T: HttpTransport + 'static,
+    {
+        Cassette {
+            path: path.into(),
+            mode: Mode::Record,
+            options: options.clone(),
+            transport: Some(Arc::new(transport)),
+            state: Arc::default(),
+        }
+    }
+
+    /// Answer requests with the interactions recorded in the file at `path`
+    pub fn replay<P>(path: P, options: &CassetteOptions) -> Result<Self>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let recording: Recording = serde_json::from_slice(&fs::read(&path)?)?;
+        let plays = vec![0; recording.interactions.len()];
+        Ok(Cassette {
+            path,
+            mode: Mode::Replay,
+            options: options.clone(),
+            transport: None,
+            state: Arc::new(Mutex::new(State {
+                interactions: recording.interactions,
+                plays,
+                unsaved: false,
+            })),
+        })
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of interactions recorded or loaded so far
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().interactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the interactions recorded so far to the cassette's file, on
+    /// tokio's blocking thread pool. Dropping a cassette writes any
+    /// interactions recorded since, without reporting failures
+    pub fn finish(&self) -> Future<()> {
+        let path = self.path.clone();
+        let state = self.state.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || save(&path, &state))
+                .await
+                .map_err(|e| Error::from(io::Error::other(e)))?
+        })
+    }
+
+    fn replay_request(&self, request: &Request<Vec<u8>>) -> Result<Response<Bytes>> {
+        let mut state = self.state.lock().unwrap();
+        let candidates: Vec<usize> = state
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.request.matches(request, &self.options))
+            .map(|(index, _)| index)
+            .collect();
+        // play recordings in order, repeating the last once all have been played
+        let index = candidates
+            .iter()
+            .find(|index| state.plays[**index] == 0)
+            .or_else(|| candidates.last())
+            .copied();
+        match index {
+            Some(index) => {
+                state.plays[index] += 1;
+                state.interactions[index].response.to_response()
+            }
+            None if self.options.strict => Err(ErrorKind::UnmatchedRequest {
+                method: request.method().clone(),
+                uri: redact_uri(request.uri(), &self.options),
+            }
+            .into()),
+            None => Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Bytes::from_static(
+                br#"{"message":"Not Found","documentation_url":"https://docs.github.com/rest"}"#,
+            ))?),
+        }
+    }
+}
+
+impl fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("mode", &self.mode)
+            .field("interactions", &self.len())
+            .finish()
+    }
+}
+
+impl Drop for Cassette {
+    fn drop(&mut self) {
+        if let Err(e) = save(&self.path, &self.state) {
+            warn!("Failed to write cassette {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Write the recorded interactions to a cassette's file, if there are any
+/// unsaved ones. The state stays locked while writing, so an older recording
+/// can't overwrite a newer one
+fn save(path: &Path, state: &Mutex<State>) -> Result<()> {
+    let mut state = state.lock().unwrap();
+    if !state.unsaved {
+        return Ok(());
+    }
+    let recording = serde_json::to_vec_pretty(&Recording {
+        interactions: state.interactions.clone(),
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, recording)?;
+    state.unsaved = false;
+    Ok(())
+}
+
+impl HttpTransport for Cassette {
+    fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Bytes>> {
+        let transport = match (&self.mode, &self.transport) {
+            (Mode::Record, Some(transport)) => transport.clone(),
+            _ => return Box::pin(future::ready(self.replay_request(&request))),
+        };
+        let recorded = RecordedRequest::new(&request, &self.options);
+        let options = self.options.clone();
+        let state = self.state.clone();
+        Box::pin(async move {
+            let response = transport.send(request).await?;
+            let mut state = state.lock().unwrap();
+            state.interactions.push(Interaction {
+                request: recorded,
+                response: RecordedResponse::new(&response, &options),
+            });
+            state.plays.push(0);
+            state.unsaved = true;
+            Ok(response)
+        })
+    }
+}
+
+// representations
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Recording {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    uri: String,
+    headers: BTreeMap<String, String>,
+    #[serde(flatten)]
+    body: RecordedBody,
+}
+
+impl RecordedRequest {
+    fn new(request: &Request<Vec<u8>>, options: &CassetteOptions) -> Self {
+        RecordedRequest {
+            method: request.method().to_string(),
+            uri: redact_uri(request.uri(), options),
+            headers: record_headers(request.headers(), options),
+            body: RecordedBody::new(request.body(), options),
+        }
+    }
+
+    fn matches(&self, request: &Request<Vec<u8>>, options: &CassetteOptions) -> bool {
+        self.method == request.method().as_str()
+            && normalize_uri(&self.uri, options)
+                == normalize_uri(&redact_uri(request.uri(), options), options)
+            && options.match_headers.iter().all(|name| {
+                let actual = request
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| redact_header(name, value, options));
+                self.headers.get(name.as_str()).map(String::as_str) == actual.as_deref()
+            })
+            && (!options.match_body
+                || self
+                    .body
+                    .matches(&RecordedBody::new(request.body(), options)))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    #[serde(flatten)]
+    body: RecordedBody,
+}
+
+impl RecordedResponse {
+    fn new(response: &Response<Bytes>, options: &CassetteOptions) -> Self {
+        RecordedResponse {
+            status: response.status().as_u16(),
+            headers: record_headers(response.headers(), options),
+            body: RecordedBody::new(response.body(), options),
+        }
+    }
+
+    fn to_response(&self) -> Result<Response<Bytes>> {
+        let mut response = Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            response = response.header(name.as_str(), value.as_str());
+        }
+        Ok(response.body(Bytes::from(self.body.bytes()?))?)
+    }
+}
+
+/// A body recorded as text when it is valid utf-8, and as base64 otherwise
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct RecordedBody {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl RecordedBody {
+    fn new(bytes: &[u8], options: &CassetteOptions) -> Self {
+        if bytes.is_empty() {
+            return RecordedBody::default();
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(text) => RecordedBody {
+                body: Some(redact(text, options)),
+                body_base64: None,
+            },
+            Err(_) => RecordedBody {
+                body: None,
+                body_base64: Some(base64::encode(bytes)),
+            },
+        }
+    }
+
+    fn bytes(&self) -> Result<Vec<u8>> {
+        match (&self.body, &self.body_base64) {
+            (Some(text), _) => Ok(text.clone().into_bytes()),
+            (None, Some(encoded)) => base64::decode(encoded).map_err(|err| {
+                Error::from(std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+            }),
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+
+    fn matches(&self, other: &RecordedBody) -> bool {
+        match (&self.body, &other.body) {
+            (Some(recorded), Some(actual)) => {
+                match (
+                    serde_json::from_str::<serde_json::Value>(recorded),
+                    serde_json::from_str::<serde_json::Value>(actual),
+                ) {
+                    (Ok(recorded), Ok(actual)) => recorded == actual,
+                    _ => recorded == actual,
+                }
+            }
+            _ => self.body == other.body && self.body_base64 == other.body_base64,
+        }
+    }
+}
+
+fn record_headers(
+    headers: &http::HeaderMap,
+    options: &CassetteOptions,
+) -> BTreeMap<String, String> {
+    let mut recorded = BTreeMap::<String, String>::new();
+    for (name, value) in headers {
+        if let Ok(value) = value.to_str() {
+            let value = redact_header(name, value, options);
+            recorded
+                .entry(name.as_str().to_string())
+                .and_modify(|existing| {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                })
+                .or_insert(value);
+        }
+    }
+    recorded
+}
+
+fn redact_header(name: &HeaderName, value: &str, options: &CassetteOptions) -> String {
+    if options.redact_headers.contains(name) {
+        REDACTED.to_string()
+    } else {
+        redact(value, options)
+    }
+}
+
+fn redact(text: &str, options: &CassetteOptions) -> String {
+    options
+        .redact_values
+        .iter()
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        })
+}
+
+/// A uri with its redacted query parameters' values and redacted values replaced
+fn redact_uri(uri: &Uri, options: &CassetteOptions) -> String {
+    let uri = uri.to_string();
+    let mut url = match Url::parse(&uri) {
+        Ok(url) => url,
+        Err(_) => return redact(&uri, options),
+    };
+    let redacted = |key: &str| options.redact_query_params.iter().any(|p| p == key);
+    if !url.query_pairs().any(|(key, _)| redacted(&key)) {
+        return redact(&uri, options);
+    }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| {
+            let value = if redacted(&key) {
+                REDACTED.to_string()
+            } else {
+                value.into_owned()
+            };
+            (key.into_owned(), value)
+        })
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    redact(url.as_str(), options)
+}
+
+/// A uri with its query parameters sorted and ignored ones removed
+fn normalize_uri(uri: &str, options: &CassetteOptions) -> String {
+    let mut url = match Url::parse(uri) {
+        Ok(url) => url,
+        Err(_) => return uri.to_string(),
+    };
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !options.ignore_query_params.iter().any(|p| p == key))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    pairs.sort();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Credentials, Github};
+    use http::Method;
+    use std::env;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A transport answering every request with its own method and uri
+    #[derive(Debug, Default)]
+    struct Echo {
+        sent: AtomicUsize,
+    }
+
+    impl HttpTransport for Echo {
+        fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Bytes>> {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            let body = serde_json::json!({
+                "method": request.method().as_str(),
+                "uri": request.uri().to_string(),
+                "token": "s3cr3t",
+            });
+            Box::pin(future::ok(
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .header("content-type", "application/json")
+                    .body(Bytes::from(body.to_string()))
+                    .unwrap(),
+            ))
+        }
+    }
+
+    fn path(name: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("hubcaps-cassettes-{}", std::process::id()))
+            .join(name)
+    }
+
+    fn github<T>(transport: T) -> Github
+    where
+        T: HttpTransport + 'static,
+    {
+        Github::builder("test-agent")
+            .credentials(Credentials::Token("s3cr3t".into()))
+            .transport(transport)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn records_and_replays() {
+        let path = path("roundtrip.json");
+        let options = CassetteOptions::builder().redact_value("s3cr3t").build();
+
+        let echo = Arc::new(Echo::default());
+        let recorded: serde_json::Value = github(Cassette::record(&path, echo.clone(), &options))
+            .get("/user?b=2&a=1")
+            .await
+            .unwrap();
+        assert_eq!(echo.sent.load(Ordering::SeqCst), 1);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("s3cr3t"));
+        assert!(contents.contains(REDACTED));
+
+        let replayed: serde_json::Value = github(Cassette::replay(&path, &options).unwrap())
+            .get("/user?a=1&b=2")
+            .await
+            .unwrap();
+        assert_eq!(replayed["uri"], recorded["uri"]);
+        assert_eq!(replayed["token"], REDACTED);
+    }
+
+    #[tokio::test]
+    async fn records_overlapping_requests() {
+        let path = path("overlapping.json");
+        let cassette = Arc::new(Cassette::record(
+            &path,
+            Echo::default(),
+            &CassetteOptions::default(),
+        ));
+        let github = github(cassette.clone());
+        let users = (0..8).map(|n| github.get::<serde_json::Value>(&format!("/users/{}", n)));
+        future::try_join_all(users).await.unwrap();
+        assert!(!path.exists(), "interactions are written once finished");
+
+        cassette.finish().await.unwrap();
+        let recording: Recording = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(recording.interactions.len(), 8);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn strict_replay_fails_unmatched_requests() {
+        let path = path("strict.json");
+        let options = CassetteOptions::default();
+        github(Cassette::record(&path, Echo::default(), &options))
+            .get::<serde_json::Value>("/user")
+            .await
+            .unwrap();
+
+        let github = github(Cassette::replay(&path, &options).unwrap());
+        match github
+            .get::<serde_json::Value>("/users/octocat")
+            .await
+            .map_err(Error::into_kind)
+        {
+            Err(ErrorKind::UnmatchedRequest { method, uri }) => {
+                assert_eq!(method, Method::GET);
+                assert_eq!(uri, "https://api.github.com/users/octocat");
+            }
+            otherwise => panic!("expected an unmatched request, got {:?}", otherwise),
+        }
+
+        let lenient = CassetteOptions::builder().strict(false).build();
+        let github = super::tests::github(Cassette::replay(&path, &lenient).unwrap());
+        match github
+            .get::<serde_json::Value>("/users/octocat")
+            .await
+            .map_err(Error::into_kind)
+        {
+            Err(ErrorKind::Fault { code, .. }) => assert_eq!(code, StatusCode::NOT_FOUND),
+            otherwise => panic!("expected a 404, got {:?}", otherwise),
+        }
+    }
+
+    #[tokio::test]
+    async fn redacts_client_credentials() {
+        let path = path("client.json");
+        let options = CassetteOptions::default();
+        let client = |transport| {
+            Github::builder("test-agent")
+                .credentials(Credentials::Client("id".into(), "s3cr3t".into()))
+                .transport(transport)
+                .build()
+                .unwrap()
+        };
+        client(Cassette::record(&path, Echo::default(), &options))
+            .get::<serde_json::Value>("/user")
+            .await
+            .unwrap();
+        let recording: Recording = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(
+            recording.interactions[0].request.uri,
+            "https://api.github.com/user?client_id=%5BREDACTED%5D&client_secret=%5BREDACTED%5D"
+        );
+
+        let github = client(Cassette::replay(&path, &options).unwrap());
+        github.get::<serde_json::Value>("/user").await.unwrap();
+        match github
+            .get::<serde_json::Value>("/users/octocat")
+            .await
+            .map_err(Error::into_kind)
+        {
+            Err(ErrorKind::UnmatchedRequest { uri, .. }) => {
+                assert!(!uri.contains("s3cr3t"));
+                assert!(uri.contains("client_secret=%5BREDACTED%5D"));
+            }
+            otherwise => panic!("expected an unmatched request, got {:?}", otherwise),
+        }
+    }
+
+    #[test]
+    fn matching_rules() {
+        let options = CassetteOptions::builder()
+            .ignore_query_params(vec!["since"])
+            .build();
+        let request = |uri: &str, accept: &str, auth: &str, body: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header(ACCEPT, accept)
+                .header(AUTHORIZATION, auth)
+                .body(body.as_bytes().to_vec())
+                .unwrap()
+        };
+        let recorded = RecordedRequest::new(
+            &request(
+                "https://api.github.com/x?a=1&since=1",
+                "application/json",
+                "token one",
+                r#"{"a":1,"b":2}"#,
+            ),
+            &options,
+        );
+        assert_eq!(recorded.headers["authorization"], REDACTED);
+        assert!(recorded.matches(
+            &request(
+                "https://api.github.com/x?since=2&a=1",
+                "application/json",
+                "token two",
+                r#"{"b": 2, "a": 1}"#,
+            ),
+            &options
+        ));
+        assert!(!recorded.matches(
+            &request(
+                "https://api.github.com/x?a=1",
+                "application/vnd.github.v3.raw",
+                "token one",
+                r#"{"a":1,"b":2}"#,
+            ),
+            &options
+        ));
+        assert!(!recorded.matches(
+            &request(
+                "https://api.github.com/x?a=1",
+                "application/json",
+                "token one",
+                r#"{"a":2}"#,
+            ),
+            &options
+        ));
+    }
+}
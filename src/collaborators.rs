@@ -1,23 +1,18 @@
 use crate::users::User;
 use crate::Future;
-use crate::{Error, Github};
+use crate::{ErrorKind, Github};
 use futures::prelude::*;
 use http::StatusCode;
 use std::collections::HashMap;
 use std::fmt;
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum Permissions {
     Admin,
+    #[default]
     Push,
     Pull,
 }
 
-impl Default for Permissions {
-    fn default() -> Permissions {
-        Permissions::Push
-    }
-}
-
 impl fmt::Display for Permissions {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -65,13 +60,17 @@ impl Collaborators {
                 .get::<()>(&self.path(&format!("/{}", username)))
                 .map_ok(|_| true)
                 .or_else(|err| async move {
-                    match err {
-                        Error::Fault {
+                    match err.kind() {
+                        ErrorKind::Fault {
+                            code: StatusCode::NOT_FOUND,
+                            ..
+                        }
+                        | ErrorKind::UnexpectedResponse {
                             code: StatusCode::NOT_FOUND,
                             ..
                         } => Ok(false),
-                        Error::Codec(_) => Ok(true),
-                        otherwise => Err(otherwise),
+                        ErrorKind::Codec(_) => Ok(true),
+                        _ => Err(err),
                     }
                 }),
         )
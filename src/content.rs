@@ -3,12 +3,13 @@ use std::fmt;
 use std::ops;
 
 use data_encoding::BASE64;
+use futures::prelude::*;
 use serde::de::{self, Visitor};
 use serde::{Deserialize, Serialize};
 
 use crate::repo_commits::CommitDetails;
 use crate::utils::{percent_encode, PATH};
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, MediaType, Stream};
 
 /// Provides access to the content information for a repository
 pub struct Content {
@@ -56,6 +57,23 @@ impl Content {
         self.github.get(&self.path(location, ref_))
     }
 
+    /// The raw contents of a file.
+    ///
+    /// Unlike `file`, this supports files up to 100 megabytes in size.
+    pub fn raw(&self, location: &str, ref_: &str) -> Future<Vec<u8>> {
+        Box::pin(
+            self.github
+                .get_bytes(&self.path(location, ref_), MediaType::Raw)
+                .map_ok(|body| body.to_vec()),
+        )
+    }
+
+    /// A file rendered as html, e.g. a README's markdown.
+    pub fn html(&self, location: &str, ref_: &str) -> Future<String> {
+        self.github
+            .get_text(&self.path(location, ref_), MediaType::Html)
+    }
+
     /// List the root directory.
     pub fn root(&self, ref_: &str) -> Stream<DirectoryItem> {
         self.iter("/", ref_)
@@ -210,9 +228,9 @@ pub struct Links {
 #[derive(Debug)]
 pub struct DecodedContents(Vec<u8>);
 
-impl Into<Vec<u8>> for DecodedContents {
-    fn into(self) -> Vec<u8> {
-        self.0
+impl From<DecodedContents> for Vec<u8> {
+    fn from(contents: DecodedContents) -> Vec<u8> {
+        contents.0
     }
 }
 
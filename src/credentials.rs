@@ -0,0 +1,302 @@
+//! Credentials managed outside of hubcaps
+//!
+//! When tokens live in a secrets manager and rotate, implement
+//! `CredentialProvider` and authenticate with `Credentials::provider`. The
+//! provider is asked for a credential the first time one is needed, then
+//! again whenever the credential it last provided is about to expire or was
+//! rejected by GitHub. Every clone of a client shares the same provider and
+//! its cached credential.
+//!
+//! ```no_run
+//! use std::time::{Duration, SystemTime};
+//!
+//! use hubcaps::credentials::{CredentialProvider, ProvidedCredential};
+//! use hubcaps::{Credentials, Future, Github};
+//!
+//! #[derive(Debug)]
+//! struct Vault;
+//!
+//! impl CredentialProvider for Vault {
+//!     fn credential(&self) -> Future<ProvidedCredential> {
+//!         Box::pin(async {
+//!             let token = std::env::var("GITHUB_TOKEN").unwrap_or_default();
+//!             Ok(ProvidedCredential::token(token)
+//!                 .expires_at(SystemTime::now() + Duration::from_secs(15 * 60)))
+//!         })
+//!     }
+//! }
+//!
+//! # fn run() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", Credentials::provider(Vault))?;
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use log::debug;
+
+use crate::Future;
+
+/// How long before a provided credential expires to ask for a new one
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Supplies credentials on behalf of a `Github` client
+pub trait CredentialProvider: fmt::Debug + Send + Sync {
+    /// Fetch a current credential
+    fn credential(&self) -> Future<ProvidedCredential>;
+}
+
+/// A credential supplied by a `CredentialProvider`
+#[derive(Clone, PartialEq)]
+pub struct ProvidedCredential {
+    /// the complete value of the `Authorization` header, e.g. `token ghp_...`
+    pub authorization: String,
+    /// when the credential stops being valid. Credentials without an expiry
+    /// are used until GitHub rejects them
+    pub expires_at: Option<SystemTime>,
+    /// who the credential authenticates as, e.g. a user or installation id,
+    /// staying the same as the credential rotates. Responses cached by an
+    /// `HttpCache` are shared by credentials with the same identity, and
+    /// otherwise only by identical credentials
+    pub identity: Option<String>,
+}
+
+impl ProvidedCredential {
+    /// An OAuth, personal access or installation token
+    pub fn token<T>(token: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        ProvidedCredential {
+            authorization: format!("token {}", token),
+            expires_at: None,
+            identity: None,
+        }
+    }
+
+    /// A bearer token, such as a GitHub App's JWT
+    pub fn bearer<T>(token: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        ProvidedCredential {
+            authorization: format!("Bearer {}", token),
+            expires_at: None,
+            identity: None,
+        }
+    }
+
+    pub fn expires_at(mut self, expires_at: SystemTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    pub fn identity<I>(mut self, identity: I) -> Self
+    where
+        I: Into<String>,
+    {
+        self.identity = Some(identity.into());
+        self
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| SystemTime::now() + REFRESH_MARGIN < expires_at)
+            .unwrap_or(true)
+    }
+}
+
+impl fmt::Debug for ProvidedCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProvidedCredential")
+            .field("authorization", &"*".repeat(self.authorization.len()))
+            .field("expires_at", &self.expires_at)
+            .field("identity", &self.identity)
+            .finish()
+    }
+}
+
+/// A stable identity of the credentials a request is sent with: a client,
+/// app or installation id, or a token. Unlike the `Authorization` header it
+/// doesn't change as installation tokens and JWTs are refreshed or provided
+/// credentials rotate. `Github` attaches it to requests as an extension
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CredentialIdentity(String);
+
+impl CredentialIdentity {
+    pub(crate) fn new<I>(identity: I) -> Self
+    where
+        I: Into<String>,
+    {
+        CredentialIdentity(identity.into())
+    }
+
+    #[cfg(feature = "httpcache")]
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl fmt::Debug for CredentialIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CredentialIdentity(*****)")
+    }
+}
+
+/// A `CredentialProvider` along with the credential it last provided, shared
+/// between clones of a client
+#[derive(Clone)]
+pub struct SharedCredentialProvider {
+    provider: Arc<dyn CredentialProvider>,
+    cached: Arc<tokio::sync::Mutex<Option<ProvidedCredential>>>,
+}
+
+impl SharedCredentialProvider {
+    pub fn new<P>(provider: P) -> Self
+    where
+        P: CredentialProvider + 'static,
+    {
+        SharedCredentialProvider {
+            provider: Arc::new(provider),
+            cached: Arc::new(tokio::sync::Mutex::new(None)),
+        }
+    }
+
+    /// The current credential, asking the provider for a new one if the
+    /// cached one is missing or about to expire. Requests waiting on the
+    /// provider share the credential it provides
+    pub(crate) fn credential(&self) -> Future<ProvidedCredential> {
+        let shared = self.clone();
+        Box::pin(async move {
+            let mut cached = shared.cached.lock().await;
+            if let Some(credential) = cached.as_ref().filter(|credential| credential.is_fresh()) {
+                return Ok(credential.clone());
+            }
+            debug!("Provided credential is missing or about to expire, refreshing");
+            let credential = shared.provider.credential().await?;
+            *cached = Some(credential.clone());
+            Ok(credential)
+        })
+    }
+
+    /// Forget the cached credential if it's the one GitHub rejected, so the
+    /// next request asks the provider for another. Waits for any refresh in
+    /// progress, which may already have replaced it
+    pub(crate) async fn reject(&self, authorization: &str) {
+        let mut cached = self.cached.lock().await;
+        if cached
+            .as_ref()
+            .map(|credential| credential.authorization == authorization)
+            .unwrap_or(false)
+        {
+            debug!("Provided credential was rejected, discarding it");
+            *cached = None;
+        }
+    }
+}
+
+impl PartialEq for SharedCredentialProvider {
+    fn eq(&self, other: &SharedCredentialProvider) -> bool {
+        Arc::ptr_eq(&self.cached, &other.cached)
+    }
+}
+
+impl fmt::Debug for SharedCredentialProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedCredentialProvider")
+            .field("provider", &self.provider)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use crate::Credentials;
+    use futures::future;
+    use http::header::AUTHORIZATION;
+    use http::StatusCode;
+
+    #[derive(Debug, Default)]
+    struct Rotating {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl CredentialProvider for Rotating {
+        fn credential(&self) -> Future<ProvidedCredential> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Box::pin(future::ok(
+                ProvidedCredential::token(format!("secret-{}", call))
+                    .expires_at(SystemTime::now() + Duration::from_secs(60 * 60)),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn credential_provider() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, "{}"),
+            (StatusCode::OK, "{}"),
+            (
+                StatusCode::UNAUTHORIZED,
+                r#"{"message": "Bad credentials"}"#,
+            ),
+            (StatusCode::OK, "{}"),
+        ]);
+        let github = transport.github(Some(Credentials::provider(Rotating::default())));
+
+        github.get::<serde_json::Value>("/user").await.unwrap();
+        // clones share the provided credential
+        github
+            .clone()
+            .get::<serde_json::Value>("/user")
+            .await
+            .unwrap();
+        // a rejected credential is replaced by the next request
+        assert!(github.get::<serde_json::Value>("/user").await.is_err());
+        github.get::<serde_json::Value>("/user").await.unwrap();
+
+        let auth: Vec<_> = transport
+            .requests()
+            .iter()
+            .map(|request| {
+                request.headers()[AUTHORIZATION]
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            auth,
+            vec![
+                "token secret-0",
+                "token secret-0",
+                "token secret-0",
+                "token secret-1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejections_wait_for_the_lock() {
+        let shared = SharedCredentialProvider::new(Rotating::default());
+        let authorization = shared.credential().await.unwrap().authorization;
+        let guard = shared.cached.lock().await;
+        let rejection = tokio::spawn({
+            let shared = shared.clone();
+            let authorization = authorization.clone();
+            async move { shared.reject(&authorization).await }
+        });
+        tokio::task::yield_now().await;
+        drop(guard);
+        rejection.await.unwrap();
+        assert!(shared.cached.lock().await.is_none());
+        assert_ne!(
+            shared.credential().await.unwrap().authorization,
+            authorization
+        );
+    }
+}
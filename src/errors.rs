@@ -1,6 +1,8 @@
 //! Client errors
+use crate::graphql::GraphQLError;
 use crate::jwt::errors::Error as JWTError;
-use http::StatusCode;
+use http::Error as HttpError;
+use http::{HeaderMap, Method, Response, StatusCode};
 use reqwest::Error as ReqwestError;
 use serde::Deserialize;
 use serde_json::error::Error as SerdeError;
@@ -14,84 +16,329 @@ use url::ParseError;
 /// A standard result type capturing common errors for all GitHub operations
 pub type Result<T> = StdResult<T, Error>;
 
+/// An error along with the context of the request it was caused by, if any
 #[derive(Debug)]
-pub enum Error {
+pub struct Error {
+    kind: ErrorKind,
+    context: Option<Box<RequestContext>>,
+}
+
+impl Error {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The request which failed, for errors which occurred sending a request
+    /// or handling its response
+    pub fn context(&self) -> Option<&RequestContext> {
+        self.context.as_deref()
+    }
+
+    /// The status of the response which caused this error, if any
+    pub fn status(&self) -> Option<StatusCode> {
+        match &self.kind {
+            ErrorKind::Fault { code, .. } | ErrorKind::UnexpectedResponse { code, .. } => {
+                Some(*code)
+            }
+            _ => self.context().and_then(|context| context.status),
+        }
+    }
+
+    /// GitHub's `x-github-request-id` of the request which caused this error,
+    /// worth including when reaching out to GitHub support
+    pub fn request_id(&self) -> Option<&str> {
+        self.context().and_then(RequestContext::request_id)
+    }
+
+    /// Attach the context of the request this error was caused by, unless it
+    /// already has one
+    pub(crate) fn with_context(mut self, context: RequestContext) -> Self {
+        if self.context.is_none() {
+            self.context = Some(Box::new(context));
+        }
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
     /// Client side error returned for faulty requests
     Fault {
         code: StatusCode,
         error: ClientError,
     },
+    /// Error kind returned for unsuccessful responses whose body isn't a GitHub
+    /// error, e.g. the HTML pages of proxies and load balancers
+    UnexpectedResponse { code: StatusCode, body: String },
     /// Error kind returned when a credential's rate limit has been exhausted. Wait for the reset duration before issuing more requests
     RateLimit { reset: Duration },
+    /// Error kind returned when a secondary rate limit was hit, typically for making too many
+    /// requests in a short period of time. Wait for the `retry_after` duration before issuing more
+    /// requests. `resource` names the rate limited resource, when GitHub reports it
+    SecondaryRateLimit {
+        retry_after: Duration,
+        resource: Option<String>,
+    },
+    /// Errors reported by a GraphQL query
+    GraphQL(Vec<GraphQLError>),
+    /// Error kind returned when GitHub refuses to grant an OAuth access token,
+    /// e.g. `access_denied` or `expired_token`
+    OAuth {
+        error: String,
+        description: Option<String>,
+    },
+    /// Error kind returned when exchanging OAuth codes or refresh tokens without
+    /// a client secret
+    MissingClientSecret,
+    /// Error kind returned by a strict `Cassette` replaying a request it has no recording for
+    UnmatchedRequest { method: Method, uri: String },
+    /// Error kind returned for requests made in `CacheMode::Offline` without a cached response
+    NotCached { uri: String },
+    /// Error kind returned for raw requests to absolute urls on hosts other than the client's
+    /// api, uploads and GraphQL hosts, which would otherwise be sent the client's credentials
+    ForeignUrl { url: String },
     /// Serialization related errors
     Codec(SerdeError),
     /// HTTP client errors
     Reqwest(ReqwestError),
+    /// HTTP request construction errors
+    Http(HttpError),
     /// Url format errors
     Url(ParseError),
     /// Network errors
     IO(IoError),
     /// JWT validation errors
     JWT(JWTError),
+    /// Error kind returned for GitHub App private keys which can't sign tokens
+    InvalidPrivateKey(JWTError),
+}
+
+/// The response headers kept in a `RequestContext`
+const CONTEXT_HEADERS: &[&str] = &[
+    "x-github-request-id",
+    "x-oauth-scopes",
+    "x-accepted-oauth-scopes",
+    "x-github-sso",
+    "x-github-media-type",
+];
+
+/// The credential-free url a request's `RequestContext` reports, attached to
+/// requests as an extension for the `HttpCache` to report in
+/// `ErrorKind::NotCached` errors
+#[cfg(feature = "httpcache")]
+#[derive(Clone, Debug)]
+pub(crate) struct ContextUrl(pub(crate) String);
+
+/// Describes the request an error was caused by
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    pub method: Method,
+    /// the requested url, without any credentials
+    pub url: String,
+    /// the response status, if a response was received
+    pub status: Option<StatusCode>,
+    /// the response headers relevant to troubleshooting, such as
+    /// `x-github-request-id` and the oauth scope headers
+    pub headers: HeaderMap,
+}
+
+impl RequestContext {
+    pub(crate) fn new<B>(method: &Method, url: &str, response: Option<&Response<B>>) -> Self {
+        let mut headers = HeaderMap::new();
+        if let Some(response) = response {
+            for name in CONTEXT_HEADERS {
+                for value in response.headers().get_all(*name) {
+                    headers.append(*name, value.clone());
+                }
+            }
+        }
+        RequestContext {
+            method: method.clone(),
+            url: url.into(),
+            status: response.map(Response::status),
+            headers,
+        }
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.header("x-github-request-id")
+    }
+
+    /// The scopes the credentials used were granted
+    pub fn oauth_scopes(&self) -> Vec<&str> {
+        scopes(self.header("x-oauth-scopes"))
+    }
+
+    /// The scopes the requested endpoint accepts
+    pub fn accepted_oauth_scopes(&self) -> Vec<&str> {
+        scopes(self.header("x-accepted-oauth-scopes"))
+    }
+
+    /// Set when an organization's SAML SSO requires the credentials used to
+    /// be authorized, e.g. `required; url=https://github.com/orgs/...`
+    pub fn sso(&self) -> Option<&str> {
+        self.header("x-github-sso")
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+fn scopes(header: Option<&str>) -> Vec<&str> {
+    header
+        .map(|scopes| {
+            scopes
+                .split(',')
+                .map(str::trim)
+                .filter(|scope| !scope.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error {
+            kind,
+            context: None,
+        }
+    }
 }
 
 impl From<SerdeError> for Error {
     fn from(err: SerdeError) -> Self {
-        Error::Codec(err)
+        ErrorKind::Codec(err).into()
     }
 }
 
 impl From<ReqwestError> for Error {
     fn from(err: ReqwestError) -> Self {
-        Error::Reqwest(err)
+        ErrorKind::Reqwest(err).into()
+    }
+}
+
+impl From<HttpError> for Error {
+    fn from(err: HttpError) -> Self {
+        ErrorKind::Http(err).into()
     }
 }
 
 impl From<ParseError> for Error {
     fn from(err: ParseError) -> Self {
-        Error::Url(err)
+        ErrorKind::Url(err).into()
     }
 }
 
 impl From<IoError> for Error {
     fn from(err: IoError) -> Self {
-        Error::IO(err)
+        ErrorKind::IO(err).into()
     }
 }
 
 impl From<JWTError> for Error {
     fn from(err: JWTError) -> Self {
-        Error::JWT(err)
+        ErrorKind::JWT(err).into()
     }
 }
 
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
-        match self {
-            Error::Codec(err) => Some(err),
-            Error::Reqwest(err) => Some(err),
-            Error::Url(err) => Some(err),
-            Error::IO(err) => Some(err),
-            Error::JWT(err) => Some(err),
+        match &self.kind {
+            ErrorKind::Codec(err) => Some(err),
+            ErrorKind::Reqwest(err) => Some(err),
+            ErrorKind::Http(err) => Some(err),
+            ErrorKind::Url(err) => Some(err),
+            ErrorKind::IO(err) => Some(err),
+            ErrorKind::JWT(err) => Some(err),
+            ErrorKind::InvalidPrivateKey(err) => Some(err),
             _ => None,
         }
     }
 }
 
 impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(context) = self.context() {
+            write!(f, " ({} {}", context.method, context.url)?;
+            if let Some(request_id) = context.request_id() {
+                write!(f, ", request id {}", request_id)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::Fault { code, error } => write!(f, "{}: {}", code, error.message),
-            Error::RateLimit { reset } => write!(
+            ErrorKind::Fault { code, error } => write!(f, "{}: {}", code, error.message),
+            ErrorKind::UnexpectedResponse { code, body } => {
+                write!(
+                    f,
+                    "{}: {}",
+                    code,
+                    body.chars().take(200).collect::<String>()
+                )
+            }
+            ErrorKind::RateLimit { reset } => write!(
                 f,
                 "Rate limit exhausted. Will reset in {} seconds",
                 reset.as_secs()
             ),
-            Error::Codec(err) => write!(f, "{}", err),
-            Error::Reqwest(err) => write!(f, "{}", err),
-            Error::Url(err) => write!(f, "{}", err),
-            Error::IO(err) => write!(f, "{}", err),
-            Error::JWT(err) => write!(f, "{}", err),
+            ErrorKind::SecondaryRateLimit {
+                retry_after,
+                resource,
+            } => write!(
+                f,
+                "Secondary rate limit{} exceeded. Retry after {} seconds",
+                resource
+                    .as_ref()
+                    .map(|resource| format!(" for {}", resource))
+                    .unwrap_or_default(),
+                retry_after.as_secs()
+            ),
+            ErrorKind::GraphQL(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ErrorKind::OAuth { error, description } => match description {
+                Some(description) => write!(f, "{}: {}", error, description),
+                None => write!(f, "{}", error),
+            },
+            ErrorKind::MissingClientSecret => write!(
+                f,
+                "The web application flow and token refreshes require a client secret"
+            ),
+            ErrorKind::UnmatchedRequest { method, uri } => {
+                write!(f, "No recorded interaction matches {} {}", method, uri)
+            }
+            ErrorKind::NotCached { uri } => write!(f, "No cached response for {}", uri),
+            ErrorKind::ForeignUrl { url } => {
+                write!(f, "Refusing to send credentials to {}", url)
+            }
+            ErrorKind::Codec(err) => write!(f, "{}", err),
+            ErrorKind::Reqwest(err) => write!(f, "{}", err),
+            ErrorKind::Http(err) => write!(f, "{}", err),
+            ErrorKind::Url(err) => write!(f, "{}", err),
+            ErrorKind::IO(err) => write!(f, "{}", err),
+            ErrorKind::JWT(err) => write!(f, "{}", err),
+            ErrorKind::InvalidPrivateKey(err) => write!(
+                f,
+                "Invalid private key, expected a PEM (PKCS#1 or PKCS#8) or DER encoded RSA key: {}",
+                err
+            ),
         }
     }
 }
@@ -117,10 +364,13 @@ pub struct ClientError {
 #[cfg(test)]
 mod tests {
     use super::{ClientError, FieldErr};
+    use crate::transport::scripted::Scripted;
+    use crate::ErrorKind;
+    use http::{Method, StatusCode};
 
     #[test]
     fn deserialize_client_field_errors() {
-        for (json, expect) in vec![
+        for (json, expect) in [
             // see https://github.com/softprops/hubcaps/issues/31
             (
                 r#"{"message": "Validation Failed","errors":
@@ -165,4 +415,48 @@ mod tests {
         };
         assert_eq!(serde_json::from_value::<ClientError>(json).unwrap(), expect)
     }
+
+    #[tokio::test]
+    async fn error_context() {
+        let transport = Scripted::new(vec![
+            (StatusCode::NOT_FOUND, r#"{"message": "Not Found"}"#),
+            (
+                StatusCode::BAD_GATEWAY,
+                "<html><h1>502 Bad Gateway</h1></html>",
+            ),
+        ])
+        .header(0, "x-github-request-id", "CAFE:1234")
+        .header(0, "x-oauth-scopes", "repo, user")
+        .header(0, "x-accepted-oauth-scopes", "repo")
+        .header(
+            0,
+            "x-github-sso",
+            "required; url=https://github.com/orgs/acme/sso",
+        );
+        let github = transport.github(None);
+
+        let err = github.repo("acme", "secret").get().await.unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+        assert_eq!(err.request_id(), Some("CAFE:1234"));
+        let context = err.context().unwrap();
+        assert_eq!(context.method, Method::GET);
+        assert_eq!(context.url, "https://api.github.com/repos/acme/secret");
+        assert_eq!(context.oauth_scopes(), vec!["repo", "user"]);
+        assert_eq!(context.accepted_oauth_scopes(), vec!["repo"]);
+        assert!(context.sso().unwrap().starts_with("required"));
+        assert_eq!(
+            err.to_string(),
+            "404 Not Found: Not Found (GET https://api.github.com/repos/acme/secret, request id CAFE:1234)"
+        );
+
+        let err = github.repo("acme", "secret").get().await.unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::BAD_GATEWAY));
+        match err.into_kind() {
+            ErrorKind::UnexpectedResponse { code, body } => {
+                assert_eq!(code, StatusCode::BAD_GATEWAY);
+                assert_eq!(body, "<html><h1>502 Bad Gateway</h1></html>");
+            }
+            otherwise => panic!("expected an unexpected response, got {:?}", otherwise),
+        }
+    }
 }
@@ -1,6 +1,7 @@
 //! Gists interface
+use crate::pagination::Page;
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Stream};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -24,12 +25,31 @@ impl UserGists {
         }
     }
 
-    pub fn list(&self, options: &GistListOptions) -> Future<Vec<Gist>> {
+    fn uri(&self, options: &GistListOptions) -> String {
         let mut uri = vec![format!("/users/{}/gists", self.owner)];
         if let Some(query) = options.serialize() {
             uri.push(query);
         }
-        self.github.get(&uri.join("?"))
+        uri.join("?")
+    }
+
+    pub fn list(&self, options: &GistListOptions) -> Future<Vec<Gist>> {
+        self.github.get(&self.uri(options))
+    }
+
+    /// provides a stream over all pages of this user's gists
+    pub fn iter(&self, options: &GistListOptions) -> Stream<Gist> {
+        self.github.get_stream(&self.uri(options))
+    }
+
+    /// provides a stream over each page of this user's gists
+    pub fn pages(&self, options: &GistListOptions) -> Stream<Page<Gist>> {
+        self.github.get_page_stream(&self.uri(options))
+    }
+
+    /// fetch a single page of this user's gists, starting at page 1
+    pub fn page(&self, options: &GistListOptions, number: u32) -> Future<Page<Gist>> {
+        self.github.get_page(&self.uri(options), number)
     }
 }
 
@@ -77,12 +97,31 @@ impl Gists {
         self.github.get(&self.path(&format!("/{}/{}", id, sha)))
     }
 
-    pub fn list(&self, options: &GistListOptions) -> Future<Vec<Gist>> {
+    fn uri(&self, options: &GistListOptions) -> String {
         let mut uri = vec![self.path("")];
         if let Some(query) = options.serialize() {
             uri.push(query);
         }
-        self.github.get::<Vec<Gist>>(&uri.join("?"))
+        uri.join("?")
+    }
+
+    pub fn list(&self, options: &GistListOptions) -> Future<Vec<Gist>> {
+        self.github.get::<Vec<Gist>>(&self.uri(options))
+    }
+
+    /// provides a stream over all pages of the authenticated user's gists
+    pub fn iter(&self, options: &GistListOptions) -> Stream<Gist> {
+        self.github.get_stream(&self.uri(options))
+    }
+
+    /// provides a stream over each page of the authenticated user's gists
+    pub fn pages(&self, options: &GistListOptions) -> Stream<Page<Gist>> {
+        self.github.get_page_stream(&self.uri(options))
+    }
+
+    /// fetch a single page of the authenticated user's gists, starting at page 1
+    pub fn page(&self, options: &GistListOptions, number: u32) -> Future<Page<Gist>> {
+        self.github.get_page(&self.uri(options), number)
     }
 
     pub fn public(&self) -> Future<Vec<Gist>> {
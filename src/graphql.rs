@@ -0,0 +1,394 @@
+//! GraphQL interface
+//!
+//! Some of GitHub's functionality is only available through its
+//! [GraphQL api](https://docs.github.com/en/graphql). Queries are sent with the
+//! same credentials as the rest of a `Github` client's requests.
+//!
+//! ```no_run
+//! use futures::prelude::*;
+//! use hubcaps::graphql::Connection;
+//! use hubcaps::{Credentials, Github};
+//! use serde::Deserialize;
+//! use serde_json::json;
+//!
+//! #[derive(Deserialize)]
+//! struct Data {
+//!     repository: Repository,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Repository {
+//!     discussions: Connection<Discussion>,
+//! }
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Discussion {
+//!     title: String,
+//! }
+//!
+//! const QUERY: &str = "query($owner: String!, $name: String!, $cursor: String) {
+//!   repository(owner: $owner, name: $name) {
+//!     discussions(first: 100, after: $cursor) {
+//!       nodes { title }
+//!       pageInfo { hasNextPage endCursor }
+//!     }
+//!   }
+//! }";
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new(
+//!     "user-agent-name",
+//!     Credentials::Token("personal-access-token".into()),
+//! )?;
+//! github
+//!     .graphql()
+//!     .iter(
+//!         QUERY,
+//!         json!({ "owner": "softprops", "name": "hubcaps" }),
+//!         |data: Data| data.repository.discussions,
+//!     )
+//!     .try_for_each(|discussion| async move {
+//!         println!("{:?}", discussion);
+//!         Ok(())
+//!     })
+//!     .await
+//! # }
+//! ```
+use std::fmt;
+use std::sync::Arc;
+
+use futures::prelude::*;
+use futures::stream;
+use http::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    AuthenticationConstraint, Error, ErrorKind, Future, Github, MediaType, Result, Stream,
+};
+
+/// Provides access to GitHub's GraphQL api
+pub struct GraphQL {
+    github: Github,
+}
+
+impl GraphQL {
+    #[doc(hidden)]
+    pub fn new(github: Github) -> Self {
+        Self { github }
+    }
+
+    /// The GraphQL endpoint, `/api/graphql` for GitHub Enterprise hosts
+    fn url(&self) -> String {
+        self.github.graphql_url.clone()
+    }
+
+    /// Execute a query, decoding its `data`. Fails with `ErrorKind::GraphQL` if
+    /// the response reports any errors
+    pub fn query<V, D>(&self, query: &str, variables: V) -> Future<D>
+    where
+        V: Serialize,
+        D: DeserializeOwned + Send + 'static,
+    {
+        Box::pin(
+            self.execute(query, variables)
+                .and_then(|response| future::ready(response.into_result())),
+        )
+    }
+
+    /// Execute a query, returning its data along with any errors and the
+    /// query's rate limit cost, when the query selects `rateLimit`
+    pub fn execute<V, D>(&self, query: &str, variables: V) -> Future<GraphQLResponse<D>>
+    where
+        V: Serialize,
+        D: DeserializeOwned + Send + 'static,
+    {
+        let body = json_lit!({ "query": query, "variables": variables });
+        Box::pin(
+            self.github
+                .request_entity::<Value>(
+                    Method::POST,
+                    &self.url(),
+                    Some(body),
+                    MediaType::Json,
+                    AuthenticationConstraint::Unconstrained,
+                )
+                .and_then(|raw| future::ready(GraphQLResponse::decode(raw))),
+        )
+    }
+
+    /// Stream every node of a paginated connection. The query must declare a
+    /// `$cursor: String` variable passed as the connection's `after` argument
+    /// and select its `pageInfo { hasNextPage endCursor }`. `connection`
+    /// extracts the paginated connection from each page's data
+    pub fn iter<V, D, T, F>(&self, query: &str, variables: V, connection: F) -> Stream<T>
+    where
+        V: Serialize,
+        D: DeserializeOwned + Send + 'static,
+        T: Send + 'static,
+        F: Fn(D) -> Connection<T> + Send + Sync + 'static,
+    {
+        let variables = match serde_json::to_value(variables) {
+            Ok(Value::Object(variables)) => variables,
+            Ok(Value::Null) => Default::default(),
+            Ok(_) => {
+                return Box::pin(stream::once(future::err(
+                    ErrorKind::GraphQL(vec![GraphQLError::new(
+                        "variables must serialize to an object",
+                    )])
+                    .into(),
+                )))
+            }
+            Err(err) => return Box::pin(stream::once(future::err(err.into()))),
+        };
+        let graphql = GraphQL::new(self.github.clone());
+        let query = query.to_string();
+        let connection = Arc::new(connection);
+        Box::pin(
+            stream::try_unfold(Some(None), move |cursor: Option<Option<String>>| {
+                let page = cursor.map(|cursor| {
+                    let mut variables = variables.clone();
+                    variables.insert("cursor".into(), cursor.into());
+                    graphql.query::<_, D>(&query, variables)
+                });
+                let connection = connection.clone();
+                async move {
+                    let page = match page {
+                        Some(page) => connection(page.await?),
+                        None => return Ok::<_, Error>(None),
+                    };
+                    let next = page
+                        .page_info
+                        .has_next_page
+                        .then(|| page.page_info.end_cursor.clone());
+                    Ok(Some((page.nodes, next.filter(Option::is_some))))
+                }
+            })
+            .map_ok(|nodes| stream::iter(nodes.into_iter().map(Ok)))
+            .try_flatten(),
+        )
+    }
+}
+
+// representations
+
+/// A decoded GraphQL response
+#[derive(Debug)]
+pub struct GraphQLResponse<D> {
+    /// `None` when the query failed entirely
+    pub data: Option<D>,
+    pub errors: Vec<GraphQLError>,
+    /// Present when the query selects `rateLimit { cost limit remaining resetAt nodeCount }`
+    pub rate_limit: Option<GraphQLRateLimit>,
+}
+
+impl<D> GraphQLResponse<D>
+where
+    D: DeserializeOwned,
+{
+    fn decode(mut raw: Value) -> Result<Self> {
+        let errors = match raw.get_mut("errors").map(Value::take) {
+            Some(errors) if !errors.is_null() => serde_json::from_value(errors)?,
+            _ => Vec::new(),
+        };
+        let rate_limit = match raw.pointer("/data/rateLimit").cloned() {
+            Some(rate_limit) if !rate_limit.is_null() => Some(serde_json::from_value(rate_limit)?),
+            _ => None,
+        };
+        let data = match raw.get_mut("data").map(Value::take) {
+            Some(data) if !data.is_null() => Some(serde_json::from_value(data)?),
+            _ => None,
+        };
+        Ok(GraphQLResponse {
+            data,
+            errors,
+            rate_limit,
+        })
+    }
+}
+
+impl<D> GraphQLResponse<D> {
+    /// The response's data, or its errors if there were any
+    pub fn into_result(self) -> Result<D> {
+        match (self.data, self.errors) {
+            (Some(data), errors) if errors.is_empty() => Ok(data),
+            (_, errors) if !errors.is_empty() => Err(ErrorKind::GraphQL(errors).into()),
+            _ => Err(ErrorKind::GraphQL(vec![GraphQLError::new(
+                "response contained neither data nor errors",
+            )])
+            .into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct GraphQLError {
+    pub message: String,
+    /// e.g. `NOT_FOUND`, `FORBIDDEN` or `RATE_LIMITED`
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    #[serde(default)]
+    pub path: Vec<Value>,
+    #[serde(default)]
+    pub locations: Vec<GraphQLErrorLocation>,
+}
+
+impl GraphQLError {
+    fn new(message: &str) -> Self {
+        GraphQLError {
+            message: message.into(),
+            error_type: None,
+            path: Vec::new(),
+            locations: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error_type {
+            Some(error_type) => write!(f, "{}: {}", error_type, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct GraphQLErrorLocation {
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRateLimit {
+    /// points this query cost
+    pub cost: u32,
+    pub limit: Option<u32>,
+    pub remaining: Option<u32>,
+    pub reset_at: Option<String>,
+    pub node_count: Option<u32>,
+}
+
+/// A page of a paginated GraphQL connection
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection<T> {
+    #[serde(default = "Vec::new")]
+    pub nodes: Vec<T>,
+    pub page_info: PageInfo,
+    pub total_count: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::HttpTransport;
+    use bytes::Bytes;
+
+    /// Serves a connection of five numbers, two per page
+    #[derive(Debug)]
+    struct Pages;
+
+    impl HttpTransport for Pages {
+        fn send(&self, request: http::Request<Vec<u8>>) -> Future<http::Response<Bytes>> {
+            assert_eq!(request.uri(), "https://api.github.com/graphql");
+            let body: Value = serde_json::from_slice(request.body()).unwrap();
+            let start = body["variables"]["cursor"]
+                .as_str()
+                .map_or(0, |cursor| cursor.parse().unwrap());
+            let end = (start + 2).min(5);
+            let page = serde_json::json!({
+                "data": {
+                    "numbers": {
+                        "nodes": (start..end).collect::<Vec<u32>>(),
+                        "pageInfo": { "hasNextPage": end < 5, "endCursor": end.to_string() }
+                    },
+                    "rateLimit": { "cost": 1, "remaining": 4999 }
+                }
+            });
+            Box::pin(future::ok(
+                http::Response::builder()
+                    .body(Bytes::from(page.to_string()))
+                    .unwrap(),
+            ))
+        }
+    }
+
+    fn github(host: &str) -> Github {
+        Github::builder("test-agent")
+            .host(host)
+            .transport(Pages)
+            .build()
+            .unwrap()
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Numbers {
+        numbers: Connection<u32>,
+    }
+
+    #[test]
+    fn enterprise_url() {
+        assert_eq!(
+            github("https://api.github.com").graphql().url(),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            github("https://github.example.com/api/v3").graphql().url(),
+            "https://github.example.com/api/graphql"
+        );
+    }
+
+    #[tokio::test]
+    async fn paginates_connections() {
+        let numbers: Vec<u32> = github("https://api.github.com")
+            .graphql()
+            .iter("query", serde_json::json!({}), |data: Numbers| data.numbers)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(numbers, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_cost() {
+        let response = github("https://api.github.com")
+            .graphql()
+            .execute::<_, Numbers>("query", serde_json::json!({ "cursor": "4" }))
+            .await
+            .unwrap();
+        assert_eq!(response.rate_limit.unwrap().cost, 1);
+        assert_eq!(response.data.unwrap().numbers.nodes, vec![4]);
+    }
+
+    #[test]
+    fn errors() {
+        let response = GraphQLResponse::<Value>::decode(serde_json::json!({
+            "data": null,
+            "errors": [{
+                "type": "NOT_FOUND",
+                "path": ["repository"],
+                "locations": [{ "line": 1, "column": 3 }],
+                "message": "Could not resolve to a Repository with the name 'softprops/nope'."
+            }]
+        }))
+        .unwrap();
+        assert!(response.data.is_none());
+        match response.into_result().map_err(Error::into_kind) {
+            Err(ErrorKind::GraphQL(errors)) => {
+                assert_eq!(errors[0].error_type.as_deref(), Some("NOT_FOUND"));
+                assert_eq!(errors[0].path, vec![serde_json::json!("repository")]);
+            }
+            otherwise => panic!("expected graphql errors, got {:?}", otherwise),
+        }
+    }
+}
@@ -1,30 +1,421 @@
 //! Implements <https://tools.ietf.org/html/rfc7232> Conditional Requests
-use std::collections::hash_map::DefaultHasher;
-use std::ffi::OsStr;
+//!
+//! Cached responses are keyed by their request's uri, without any
+//! credentials and with its query parameters sorted, along with a scope: a
+//! hash of the identity of the credentials and the `Accept` media type the
+//! request was sent with. Clients authenticating differently, or asking for
+//! different preview media types, never see each other's responses, while
+//! refreshed installation tokens and JWTs keep using the same entries. Responses also remember the
+//! request headers named by their `Vary` header, and are only revalidated for
+//! requests sending the same values.
+//!
+//! An `HttpCache` stores each response as a single `CachedResponse` record,
+//! holding its status, headers and body. Its operations are asynchronous, so
+//! implementations can be backed by anything from the file system to an
+//! embedded key-value store or a cache service shared between processes.
+//! `CachedResponse::to_bytes` and `CachedResponse::from_bytes` give such
+//! stores a serialized form of a record.
+//!
+//! `FileBasedCache` keeps responses on disk, optionally bounded in size, doing
+//! its I/O on tokio's blocking thread pool, and `MemoryCache` keeps a bounded
+//! number of them in memory, evicting the least recently used. Both report
+//! `CacheStats` and can be invalidated by uri, either absolute or relative to
+//! the host, such as `/repos/softprops/hubcaps`.
+//!
+//! By default every request is sent and cached responses are only used to
+//! answer `304 Not Modified`s. A client's `CacheMode` can instead answer
+//! requests from the cache without sending them, or answer them from the
+//! cache while revalidating in the background.
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::fs;
-use std::hash::{Hash, Hasher};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use http::Uri;
-use log::trace;
+use bytes::Bytes;
+use http::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_LENGTH, ETAG, IF_NONE_MATCH,
+    VARY,
+};
+use http::{Method, Request, Response, StatusCode, Uri};
+use log::{debug, trace, warn};
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
 
-use crate::{Error, Result};
+use crate::credentials::CredentialIdentity;
+use crate::errors::ContextUrl;
+use crate::middleware::{Middleware, Next};
+use crate::{Error, ErrorKind, Future, Result};
 
 pub type BoxedHttpCache = Box<dyn HttpCache + Send + Sync>;
 
+/// Asynchronous storage for cached responses
 pub trait HttpCache: HttpCacheClone + Debug {
-    fn cache_response(
-        &self,
-        uri: &str,
-        body: &[u8],
-        etag: &[u8],
-        next_link: &Option<String>,
-    ) -> Result<()>;
-    fn lookup_etag(&self, uri: &str) -> Result<String>;
-    fn lookup_body(&self, uri: &str) -> Result<String>;
-    fn lookup_next_link(&self, uri: &str) -> Result<Option<String>>;
+    /// The response stored for a key, if any
+    fn lookup(&self, key: &CacheKey) -> Future<Option<CachedResponse>>;
+
+    /// Store a response, replacing any stored for the same key
+    fn store(&self, key: &CacheKey, response: CachedResponse) -> Future<()>;
+
+    /// Called when GitHub confirmed a stored response is still current with a
+    /// `304 Not Modified`
+    fn revalidated(&self, _key: &CacheKey, _response: &CachedResponse) -> Future<()> {
+        Box::pin(futures::future::ok(()))
+    }
+
+    /// Forget the responses to requests for the given uri, whatever their
+    /// scope. A uri relative to the host, such as `/repos/o/r`, applies to
+    /// every host. See `CacheKey::matches`
+    fn invalidate(&self, _uri: &str) -> Future<()> {
+        Box::pin(futures::future::ok(()))
+    }
+
+    /// Forget every response
+    fn clear(&self) -> Future<()> {
+        Box::pin(futures::future::ok(()))
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
+}
+
+/// How well a cache has been doing since it was created
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheStats {
+    /// lookups finding a stored response
+    pub hits: u64,
+    /// lookups finding no stored response
+    pub misses: u64,
+    /// `304 Not Modified` responses answered with a stored response
+    pub revalidations: u64,
+    /// size of the stored responses GitHub didn't have to send again
+    pub bytes_saved: u64,
+    /// responses dropped to stay within the cache's limits, or once expired
+    pub evictions: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    revalidations: AtomicU64,
+    bytes_saved: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Counters {
+    fn count(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+
+    fn lookup<T>(&self, found: &Option<T>) {
+        match found {
+            Some(_) => Counters::count(&self.hits, 1),
+            None => Counters::count(&self.misses, 1),
+        }
+    }
+
+    fn revalidated(&self, response: &CachedResponse) {
+        Counters::count(&self.revalidations, 1);
+        Counters::count(&self.bytes_saved, response.body.len() as u64);
+    }
+
+    fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            revalidations: self.revalidations.load(Ordering::Relaxed),
+            bytes_saved: self.bytes_saved.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Identifies a cached response
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// the request's uri, without credentials and with its query parameters
+    /// sorted
+    pub uri: String,
+    /// a hash of the credentials and media type of the request, so
+    /// differently authenticated clients don't share responses
+    pub scope: String,
+}
+
+impl CacheKey {
+    /// The key of a request authenticated with its `Authorization` header
+    pub fn new(uri: &Uri, headers: &HeaderMap) -> Self {
+        Self::scoped(
+            uri,
+            headers.get(AUTHORIZATION).map(HeaderValue::as_bytes),
+            headers,
+        )
+    }
+
+    /// The key of a request, scoped to the identity of its credentials when
+    /// `Github` attached one, which unlike the `Authorization` header
+    /// outlives refreshed and rotated tokens
+    fn for_request(request: &Request<Vec<u8>>) -> Self {
+        let credentials = match request.extensions().get::<CredentialIdentity>() {
+            Some(identity) => Some(identity.as_bytes()),
+            None => request
+                .headers()
+                .get(AUTHORIZATION)
+                .map(HeaderValue::as_bytes),
+        };
+        Self::scoped(request.uri(), credentials, request.headers())
+    }
+
+    fn scoped(uri: &Uri, credentials: Option<&[u8]>, headers: &HeaderMap) -> Self {
+        let scope = Fnv1a::new()
+            .write_value(credentials)
+            .write_value(headers.get(ACCEPT).map(HeaderValue::as_bytes))
+            .hex();
+        CacheKey {
+            uri: normalize(&uri.to_string()),
+            scope,
+        }
+    }
+
+    /// Whether this key is for requests to the given uri. A uri relative to
+    /// the host matches the requests for its path and query to any host
+    pub fn matches(&self, uri: &str) -> bool {
+        let uri = normalize(uri);
+        if self.uri == uri {
+            return true;
+        }
+        uri.starts_with('/')
+            && self
+                .uri
+                .parse::<Uri>()
+                .ok()
+                .and_then(|key| key.path_and_query().map(|path| path.as_str() == uri))
+                .unwrap_or_default()
+    }
+}
+
+/// Query parameters carrying credentials, left out of cache keys
+const CREDENTIAL_PARAMS: &[&str] = &["client_id", "client_secret"];
+
+/// A uri without credentials or a fragment, with its query parameters
+/// sorted, so equivalent requests share their responses
+fn normalize(uri: &str) -> String {
+    let uri = uri.split('#').next().unwrap_or_default();
+    let (path, query) = match uri.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (uri, ""),
+    };
+    let mut pairs: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+        .filter(|(name, _)| !CREDENTIAL_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if pairs.is_empty() {
+        return path.to_string();
+    }
+    pairs.sort();
+    let query = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish();
+    format!("{}?{}", path, query)
+}
+
+/// A fingerprint of the request headers named by a `Vary` header, or `None`
+/// if the response varies on something other than request headers. Header
+/// values are hashed, so credentials aren't written to the cache
+fn vary_fingerprint(vary: &str, headers: &HeaderMap) -> Option<String> {
+    let mut names: Vec<String> = vary
+        .split(',')
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if names.iter().any(|name| name == "*") {
+        return None;
+    }
+    names.sort();
+    names.dedup();
+    let fingerprint = names
+        .into_iter()
+        .map(|name| {
+            let mut hasher = Fnv1a::new();
+            for value in headers.get_all(name.as_str()) {
+                hasher.write_value(Some(value.as_bytes()));
+            }
+            format!("{}={}", name, hasher.hex())
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+    Some(fingerprint)
+}
+
+/// Whether a request sends the same values as the one a response was cached
+/// for, for each of the headers named in the cached fingerprint
+fn vary_matches(fingerprint: &str, headers: &HeaderMap) -> bool {
+    let vary = fingerprint
+        .split(';')
+        .filter_map(|pair| pair.split('=').next())
+        .collect::<Vec<_>>()
+        .join(",");
+    vary_fingerprint(&vary, headers).as_deref() == Some(fingerprint)
+}
+
+/// How a `Github` client uses its `HttpCache` for GET requests
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Send every request, conditional on the etag of its cached response,
+    /// and answer `304 Not Modified` responses from the cache
+    #[default]
+    Revalidate,
+    /// Answer requests from the cache without sending them, failing with
+    /// `ErrorKind::NotCached` for those without a cached response. Paginated
+    /// listings follow the pagination links of their cached pages. Requests
+    /// other than GETs are still sent
+    Offline,
+    /// Answer requests with their cached response straight away, revalidating
+    /// it in the background for the next request. Requests without a cached
+    /// response are sent as with `Revalidate`, as are all requests made outside
+    /// of a tokio runtime, which has nothing to revalidate them in the
+    /// background on
+    StaleWhileRevalidate,
+}
+
+/// A stored response
+#[derive(Clone, Debug)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    /// the fingerprint of the request headers named by the response's `Vary`
+    /// header
+    pub vary: String,
+    pub stored_at: SystemTime,
+}
+
+/// The serialized form of a `CachedResponse`, sans body
+#[derive(Serialize, Deserialize)]
+struct RecordHead {
+    status: u16,
+    headers: Vec<(String, String)>,
+    vary: String,
+    /// milliseconds since the unix epoch
+    stored_at: u64,
+}
+
+impl CachedResponse {
+    fn new(response: &Response<Bytes>, vary: String) -> Self {
+        CachedResponse {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: response.body().clone(),
+            vary,
+            stored_at: SystemTime::now(),
+        }
+    }
+
+    pub fn etag(&self) -> Option<&HeaderValue> {
+        self.headers.get(ETAG)
+    }
+
+    /// An approximation of the memory the response takes up
+    pub fn size(&self) -> usize {
+        let headers: usize = self
+            .headers
+            .iter()
+            .map(|(name, value)| name.as_str().len() + value.len())
+            .sum();
+        self.body.len() + headers + self.vary.len()
+    }
+
+    /// Serialize the response, as a line of json describing it followed by
+    /// its body
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let head = RecordHead {
+            status: self.status.as_u16(),
+            headers: self
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    let value = value.to_str().ok()?;
+                    Some((name.as_str().to_string(), value.to_string()))
+                })
+                .collect(),
+            vary: self.vary.clone(),
+            stored_at: self
+                .stored_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        };
+        let mut bytes = serde_json::to_vec(&head)?;
+        bytes.push(b'\n');
+        bytes.extend_from_slice(&self.body);
+        Ok(bytes)
+    }
+
+    /// Deserialize a response serialized with `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let newline = bytes
+            .iter()
+            .position(|b| *b == b'\n')
+            .ok_or_else(|| invalid_record("missing record head"))?;
+        let head: RecordHead = serde_json::from_slice(&bytes[..newline])?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in head.headers {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?,
+                HeaderValue::from_str(&value).map_err(http::Error::from)?,
+            );
+        }
+        Ok(CachedResponse {
+            status: StatusCode::from_u16(head.status).map_err(http::Error::from)?,
+            headers,
+            body: Bytes::copy_from_slice(&bytes[newline + 1..]),
+            vary: head.vary,
+            stored_at: UNIX_EPOCH + Duration::from_millis(head.stored_at),
+        })
+    }
+
+    /// Answer a request with this response without sending it. Its rate limit
+    /// headers are dropped as they're outdated
+    fn into_response(self) -> Response<Bytes> {
+        let mut headers = self.headers;
+        let rate_limits: Vec<HeaderName> = headers
+            .keys()
+            .filter(|name| name.as_str().starts_with("x-ratelimit-"))
+            .cloned()
+            .collect();
+        for name in rate_limits {
+            headers.remove(name);
+        }
+        let mut response = Response::new(self.body);
+        *response.status_mut() = self.status;
+        *response.headers_mut() = headers;
+        response
+    }
+
+    /// Answer a `304 Not Modified` with this response, updated with the
+    /// headers of the `304`
+    fn answer(self, not_modified: Response<Bytes>) -> Response<Bytes> {
+        let (parts, _) = not_modified.into_parts();
+        let mut headers = self.headers;
+        let mut previous = None;
+        for (name, value) in parts.headers {
+            let name = name.or(previous).expect("a first header name");
+            if name != CONTENT_LENGTH {
+                headers.insert(name.clone(), value);
+            }
+            previous = Some(name);
+        }
+        let mut response = Response::new(self.body);
+        *response.status_mut() = self.status;
+        *response.headers_mut() = headers;
+        *response.version_mut() = parts.version;
+        *response.extensions_mut() = parts.extensions;
+        response
+    }
 }
 
 impl dyn HttpCache {
@@ -45,120 +436,663 @@ impl Clone for BoxedHttpCache {
     }
 }
 
+/// Middleware making GET requests conditional on the etags of the responses
+/// stored in an `HttpCache`, answering `304 Not Modified` responses with the
+/// stored response. `Github` clients install this first in their middleware
+/// chain
+#[derive(Debug)]
+pub struct HttpCacheMiddleware {
+    cache: BoxedHttpCache,
+}
+
+impl HttpCacheMiddleware {
+    pub fn new(cache: BoxedHttpCache) -> Self {
+        HttpCacheMiddleware { cache }
+    }
+
+    async fn lookup(&self, key: &CacheKey, request: &Request<Vec<u8>>) -> Option<CachedResponse> {
+        match self.cache.lookup(key).await {
+            Ok(cached) => cached.filter(|cached| vary_matches(&cached.vary, request.headers())),
+            Err(e) => {
+                // failing to read the cache isn't fatal, so just log & swallow the error
+                debug!("Failed to look up cached response: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Send a request, conditional on the etag of its cached response if any
+    async fn revalidate(
+        &self,
+        key: &CacheKey,
+        mut request: Request<Vec<u8>>,
+        cached: Option<CachedResponse>,
+        next: Next,
+    ) -> Result<Response<Bytes>> {
+        if let Some(etag) = cached.as_ref().and_then(CachedResponse::etag) {
+            request
+                .headers_mut()
+                .entry(IF_NONE_MATCH)
+                .or_insert_with(|| etag.clone());
+        }
+        // a request made conditional by its sender is answered from the
+        // cache only if it's conditional on the stored response
+        let cached = cached.filter(|cached| {
+            cached.etag().is_some() && cached.etag() == request.headers().get(IF_NONE_MATCH)
+        });
+        let headers = request.headers().clone();
+        let response = next.run(request).await?;
+        match cached {
+            Some(cached) if response.status() == StatusCode::NOT_MODIFIED => {
+                if let Err(e) = self.cache.revalidated(key, &cached).await {
+                    debug!("Failed to record revalidation: {}", e);
+                }
+                Ok(cached.answer(response))
+            }
+            _ => {
+                if response.status().is_success() && response.headers().contains_key(ETAG) {
+                    self.store(key, &headers, &response).await;
+                }
+                Ok(response)
+            }
+        }
+    }
+
+    async fn store(&self, key: &CacheKey, request: &HeaderMap, response: &Response<Bytes>) {
+        let vary = response
+            .headers()
+            .get_all(VARY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        if let Some(vary) = vary_fingerprint(&vary, request) {
+            let cached = CachedResponse::new(response, vary);
+            if let Err(e) = self.cache.store(key, cached).await {
+                // failing to cache isn't fatal, so just log & swallow the error
+                debug!("Failed to cache response: {}", e);
+            }
+        }
+    }
+}
+
+impl Middleware for HttpCacheMiddleware {
+    fn handle(self: Arc<Self>, request: Request<Vec<u8>>, next: Next) -> Future<Response<Bytes>> {
+        Box::pin(async move {
+            if request.method() != Method::GET {
+                return next.run(request).await;
+            }
+            let mode = request
+                .extensions()
+                .get::<CacheMode>()
+                .copied()
+                .unwrap_or_default();
+            let key = CacheKey::for_request(&request);
+            let cached = self.lookup(&key, &request).await;
+            match (mode, cached) {
+                (CacheMode::Offline, Some(cached)) => Ok(cached.into_response()),
+                (CacheMode::Offline, None) => Err(ErrorKind::NotCached {
+                    uri: match request.extensions().get::<ContextUrl>() {
+                        Some(ContextUrl(url)) => url.clone(),
+                        None => request.uri().to_string(),
+                    },
+                }
+                .into()),
+                (CacheMode::StaleWhileRevalidate, Some(cached)) => {
+                    // revalidating in the background needs a tokio runtime to
+                    // spawn on, without one the response is revalidated first
+                    let runtime = match tokio::runtime::Handle::try_current() {
+                        Ok(runtime) => runtime,
+                        Err(_) => return self.revalidate(&key, request, Some(cached), next).await,
+                    };
+                    let response = cached.clone().into_response();
+                    runtime.spawn(async move {
+                        if let Err(e) = self.revalidate(&key, request, Some(cached), next).await {
+                            debug!("Failed to refresh cached response: {}", e);
+                        }
+                    });
+                    Ok(response)
+                }
+                (_, cached) => self.revalidate(&key, request, cached, next).await,
+            }
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct NoCache;
 
 impl HttpCache for NoCache {
-    fn cache_response(&self, _: &str, _: &[u8], _: &[u8], _: &Option<String>) -> Result<()> {
-        Ok(())
-    }
-    fn lookup_etag(&self, _uri: &str) -> Result<String> {
-        no_read("No etag cached")
+    fn lookup(&self, _key: &CacheKey) -> Future<Option<CachedResponse>> {
+        Box::pin(futures::future::ok(None))
     }
-    fn lookup_body(&self, _uri: &str) -> Result<String> {
-        no_read("No body cached")
-    }
-    fn lookup_next_link(&self, _uri: &str) -> Result<Option<String>> {
-        no_read("No next link cached")
+
+    fn store(&self, _key: &CacheKey, _response: CachedResponse) -> Future<()> {
+        Box::pin(futures::future::ok(()))
     }
 }
 
+/// A cache storing responses as files under a directory. Clones share their
+/// statistics
 #[derive(Clone, Debug)]
 pub struct FileBasedCache {
     root: PathBuf,
+    max_bytes: Option<u64>,
+    /// an estimate of the size of the cache, known once it's been measured
+    size: Arc<Mutex<Option<u64>>>,
+    counters: Arc<Counters>,
+    /// the entries of outdated layouts are discarded before first use
+    discard_outdated: Arc<Once>,
 }
 
 impl FileBasedCache {
-    #[doc(hidden)] // public for integration testing only
+    /// A cache stored under the given directory. Entries of the `v1` and `v2`
+    /// layouts of earlier versions can't be read, and are deleted the first
+    /// time the cache is used
     pub fn new<P: Into<PathBuf>>(root: P) -> FileBasedCache {
-        FileBasedCache { root: root.into() }
+        FileBasedCache {
+            root: root.into(),
+            max_bytes: None,
+            size: Arc::default(),
+            counters: Arc::default(),
+            discard_outdated: Arc::new(Once::new()),
+        }
     }
-}
 
-impl HttpCache for FileBasedCache {
-    fn cache_response(
-        &self,
-        uri: &str,
-        body: &[u8],
-        etag: &[u8],
-        next_link: &Option<String>,
-    ) -> Result<()> {
-        let mut path = cache_path(&self.root, &uri, "json");
-        trace!("caching body at path: {}", path.display());
+    /// Garbage collect the least recently used responses whenever the cache
+    /// grows beyond this many bytes
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Remove the least recently used responses until the cache fits within
+    /// its `max_bytes`, if it has one. Resolves to the number of responses
+    /// removed
+    pub fn gc(&self) -> Future<u64> {
+        self.on_disk(FileBasedCache::collect_garbage)
+    }
+
+    /// Run an operation on the cache's files on tokio's blocking thread pool
+    fn on_disk<T, F>(&self, operation: F) -> Future<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&FileBasedCache) -> Result<T> + Send + 'static,
+    {
+        let cache = self.clone();
+        blocking(move || {
+            cache
+                .discard_outdated
+                .call_once(|| cache.remove_outdated_layouts());
+            operation(&cache)
+        })
+    }
+
+    fn remove_outdated_layouts(&self) {
+        for layout in &["v1", "v2"] {
+            let outdated = self.root.join(layout);
+            match fs::remove_dir_all(&outdated) {
+                Ok(()) => debug!("Discarded outdated cache entries at {}", outdated.display()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => warn!(
+                    "Failed to discard outdated cache entries at {}: {}",
+                    outdated.display(),
+                    e
+                ),
+            }
+        }
+    }
+
+    fn collect_garbage(&self) -> Result<u64> {
+        let mut entries = Vec::new();
+        collect_entries(&self.root.join("v3"), &mut entries)?;
+        let mut size: u64 = entries.iter().map(|entry| entry.bytes).sum();
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => {
+                *self.size.lock().unwrap() = Some(size);
+                return Ok(0);
+            }
+        };
+        entries.sort_by_key(|entry| entry.used);
+        let mut evicted = 0;
+        for entry in entries {
+            if size <= max_bytes {
+                break;
+            }
+            fs::remove_file(&entry.path)?;
+            size -= entry.bytes;
+            evicted += 1;
+        }
+        debug!("Garbage collected {} cached responses", evicted);
+        Counters::count(&self.counters.evictions, evicted);
+        *self.size.lock().unwrap() = Some(size);
+        Ok(evicted)
+    }
+
+    /// Account for a newly written response, garbage collecting if the cache
+    /// may have grown too big
+    fn grew(&self, bytes: u64) -> Result<()> {
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return Ok(()),
+        };
+        let too_big = {
+            let mut size = self.size.lock().unwrap();
+            match size.as_mut() {
+                Some(size) => {
+                    *size += bytes;
+                    *size > max_bytes
+                }
+                None => true,
+            }
+        };
+        if too_big {
+            self.collect_garbage()?;
+        }
+        Ok(())
+    }
+
+    fn read(&self, key: &CacheKey) -> Result<Option<CachedResponse>> {
+        let path = cache_path(&self.root, key)?;
+        trace!("reading path: {}", path.display());
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        match CachedResponse::from_bytes(&bytes) {
+            Ok(response) => {
+                // mark the response as recently used, whether it's about to
+                // be revalidated or served as is
+                if let Err(e) = touch(&path) {
+                    debug!("Failed to touch {}: {}", path.display(), e);
+                }
+                Ok(Some(response))
+            }
+            Err(e) => {
+                debug!("Discarding unreadable {}: {}", path.display(), e);
+                fs::remove_file(&path)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn write(&self, key: &CacheKey, response: &CachedResponse) -> Result<()> {
+        let path = cache_path(&self.root, key)?;
+        trace!("caching response at path: {}", path.display());
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(&path, body)?;
-        path.set_extension("etag");
-        fs::write(&path, etag)?;
-        if let Some(next_link) = next_link {
-            path.set_extension("next_link");
-            fs::write(&path, next_link)?;
+        let bytes = response.to_bytes()?;
+        fs::write(&path, &bytes)?;
+        self.grew(bytes.len() as u64)
+    }
+
+    fn remove(&self, uri: &str) -> Result<()> {
+        let uri = normalize(uri);
+        let layout = self.root.join("v3");
+        for scope in subdirectories(&layout)? {
+            // a relative uri is resolved against every host with responses
+            // stored in the scope
+            let mut uris = Vec::new();
+            if uri.starts_with('/') {
+                for scheme in subdirectories(&layout.join(&scope))? {
+                    for authority in subdirectories(&layout.join(&scope).join(&scheme))? {
+                        uris.push(format!("{}://{}{}", scheme, authority, uri));
+                    }
+                }
+            } else {
+                uris.push(uri.clone());
+            }
+            for uri in uris {
+                let key = CacheKey {
+                    uri,
+                    scope: scope.clone(),
+                };
+                match fs::remove_file(cache_path(&self.root, &key)?) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => (),
+                }
+            }
         }
         Ok(())
     }
 
-    fn lookup_etag(&self, uri: &str) -> Result<String> {
-        read_to_string(cache_path(&self.root, uri, "etag"))
+    fn remove_all(&self) -> Result<()> {
+        match fs::remove_dir_all(self.root.join("v3")) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => (),
+        }
+        *self.size.lock().unwrap() = Some(0);
+        Ok(())
     }
+}
 
-    fn lookup_body(&self, uri: &str) -> Result<String> {
-        read_to_string(cache_path(&self.root, uri, "json"))
-    }
+struct FileEntry {
+    path: PathBuf,
+    bytes: u64,
+    used: SystemTime,
+}
 
-    fn lookup_next_link(&self, uri: &str) -> Result<Option<String>> {
-        let path = cache_path(&self.root, uri, "next_link");
-        if path.exists() {
-            Ok(Some(read_to_string(path)?))
+/// Gather the responses stored under a directory
+fn collect_entries(dir: &Path, entries: &mut Vec<FileEntry>) -> Result<()> {
+    let listing = match fs::read_dir(dir) {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    for dir_entry in listing {
+        let dir_entry = dir_entry?;
+        let metadata = dir_entry.metadata()?;
+        let path = dir_entry.path();
+        if metadata.is_dir() {
+            collect_entries(&path, entries)?;
         } else {
-            Ok(None)
+            entries.push(FileEntry {
+                path,
+                bytes: metadata.len(),
+                used: metadata.modified()?,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The names of the directories in a directory, if it exists
+fn subdirectories(dir: &Path) -> Result<Vec<String>> {
+    let listing = match fs::read_dir(dir) {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut names = Vec::new();
+    for dir_entry in listing {
+        let dir_entry = dir_entry?;
+        if dir_entry.file_type()?.is_dir() {
+            names.push(dir_entry.file_name().to_string_lossy().into_owned());
         }
     }
+    Ok(names)
 }
 
-/// Construct the cache path for the given URI and extension, from an initial directory.
+fn touch(path: &Path) -> io::Result<()> {
+    fs::OpenOptions::new()
+        .append(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+/// Run blocking file system operations on tokio's blocking thread pool
+fn blocking<T, F>(f: F) -> Future<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    Box::pin(async move {
+        tokio::task::spawn_blocking(f)
+            .await
+            .map_err(|e| Error::from(io::Error::other(e)))?
+    })
+}
+
+impl HttpCache for FileBasedCache {
+    fn lookup(&self, key: &CacheKey) -> Future<Option<CachedResponse>> {
+        let key = key.clone();
+        self.on_disk(move |cache| {
+            let found = cache.read(&key)?;
+            cache.counters.lookup(&found);
+            Ok(found)
+        })
+    }
+
+    fn store(&self, key: &CacheKey, response: CachedResponse) -> Future<()> {
+        let key = key.clone();
+        self.on_disk(move |cache| cache.write(&key, &response))
+    }
+
+    fn revalidated(&self, _key: &CacheKey, response: &CachedResponse) -> Future<()> {
+        // looking the response up already marked it as recently used
+        self.counters.revalidated(response);
+        Box::pin(futures::future::ok(()))
+    }
+
+    fn invalidate(&self, uri: &str) -> Future<()> {
+        let uri = uri.to_string();
+        self.on_disk(move |cache| cache.remove(&uri))
+    }
+
+    fn clear(&self) -> Future<()> {
+        self.on_disk(FileBasedCache::remove_all)
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.counters.snapshot()
+    }
+}
+
+/// A cache keeping responses in memory, evicting the least recently used
+/// ones to stay within its limits. Clones share their responses
+#[derive(Clone, Debug)]
+pub struct MemoryCache {
+    max_entries: usize,
+    max_bytes: usize,
+    ttl: Option<Duration>,
+    state: Arc<Mutex<MemoryState>>,
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    entries: HashMap<CacheKey, MemoryEntry>,
+    /// keys by when they were last used, least recently used first
+    recency: BTreeMap<u64, CacheKey>,
+    clock: u64,
+    bytes: usize,
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    response: CachedResponse,
+    stored: Instant,
+    used: u64,
+}
+
+impl MemoryCache {
+    /// A cache holding up to 1024 responses, of any size, for as long as they
+    /// aren't evicted
+    pub fn new() -> Self {
+        MemoryCache {
+            max_entries: 1024,
+            max_bytes: usize::MAX,
+            ttl: None,
+            state: Arc::default(),
+            counters: Arc::default(),
+        }
+    }
+
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Limit the total `CachedResponse::size` of the responses held
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Forget responses this long after they were stored
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// The number of responses held
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Look up a live response, marking it as recently used
+    fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let mut state = self.state.lock().unwrap();
+        let expired = match (state.entries.get(key), self.ttl) {
+            (None, _) => return None,
+            (Some(entry), Some(ttl)) => entry.stored.elapsed() >= ttl,
+            (Some(_), None) => false,
+        };
+        if expired {
+            state.remove(key);
+            Counters::count(&self.counters.evictions, 1);
+            return None;
+        }
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(key)?;
+        let used = std::mem::replace(&mut entry.used, clock);
+        let response = entry.response.clone();
+        state.recency.remove(&used);
+        state.recency.insert(clock, key.clone());
+        Some(response)
+    }
+
+    fn insert(&self, key: &CacheKey, response: CachedResponse) {
+        let mut state = self.state.lock().unwrap();
+        state.remove(key);
+        let size = response.size();
+        if size > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+        state.clock += 1;
+        let used = state.clock;
+        state.bytes += size;
+        state.recency.insert(used, key.clone());
+        state.entries.insert(
+            key.clone(),
+            MemoryEntry {
+                response,
+                stored: Instant::now(),
+                used,
+            },
+        );
+        let mut evicted = 0;
+        while state.entries.len() > self.max_entries || state.bytes > self.max_bytes {
+            let oldest = match state.recency.values().next() {
+                Some(oldest) => oldest.clone(),
+                None => break,
+            };
+            state.remove(&oldest);
+            evicted += 1;
+        }
+        Counters::count(&self.counters.evictions, evicted);
+    }
+}
+
+impl MemoryState {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.used);
+            self.bytes -= entry.response.size();
+        }
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpCache for MemoryCache {
+    fn lookup(&self, key: &CacheKey) -> Future<Option<CachedResponse>> {
+        let found = self.get(key);
+        self.counters.lookup(&found);
+        Box::pin(futures::future::ok(found))
+    }
+
+    fn store(&self, key: &CacheKey, response: CachedResponse) -> Future<()> {
+        self.insert(key, response);
+        Box::pin(futures::future::ok(()))
+    }
+
+    fn revalidated(&self, _key: &CacheKey, response: &CachedResponse) -> Future<()> {
+        self.counters.revalidated(response);
+        Box::pin(futures::future::ok(()))
+    }
+
+    fn invalidate(&self, uri: &str) -> Future<()> {
+        let mut state = self.state.lock().unwrap();
+        let keys: Vec<_> = state
+            .entries
+            .keys()
+            .filter(|key| key.matches(uri))
+            .cloned()
+            .collect();
+        for key in keys {
+            state.remove(&key);
+        }
+        Box::pin(futures::future::ok(()))
+    }
+
+    fn clear(&self) -> Future<()> {
+        *self.state.lock().unwrap() = MemoryState::default();
+        Box::pin(futures::future::ok(()))
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.counters.snapshot()
+    }
+}
+
+/// Construct the cache path for the given key, from an initial directory.
+/// Fails for keys without an absolute uri
 ///
 /// # Examples
 ///
 /// ```
 /// # use std::path::PathBuf;
-/// # use hubcaps::http_cache::cache_path;
+/// # use hubcaps::http_cache::{cache_path, CacheKey};
+/// let key = |uri: &str| CacheKey { uri: uri.into(), scope: "0123456789abcdef".into() };
 /// assert_eq!(
-///     cache_path(&PathBuf::from("/home/.hubcaps/cache"), "https://api.github.com/users/dwijnand/repos", "json"),
-///     PathBuf::from("/home/.hubcaps/cache/v1/https/api.github.com/users/dwijnand/repos.json"),
+///     cache_path(&PathBuf::from("/home/.hubcaps/cache"), &key("https://api.github.com/users/dwijnand/repos")).unwrap(),
+///     PathBuf::from("/home/.hubcaps/cache/v3/0123456789abcdef/https/api.github.com/users/dwijnand/repos.response"),
 /// );
 /// assert_eq!(
-///     cache_path(&PathBuf::from("/home/.hubcaps/cache"), "https://api.github.com/users/dwijnand/repos?page=2", "json"),
-///     PathBuf::from("/home/.hubcaps/cache/v1/https/api.github.com/users/dwijnand/repos/6dd58bde8abb0869.json"),
+///     cache_path(&PathBuf::from("/home/.hubcaps/cache"), &key("https://api.github.com/users/dwijnand/repos?page=2")).unwrap(),
+///     PathBuf::from("/home/.hubcaps/cache/v3/0123456789abcdef/https/api.github.com/users/dwijnand/repos/dbd81f79987a3779.response"),
 /// );
 /// assert_eq!(
-///     cache_path(&PathBuf::from("/home/.hubcaps/cache"), "https://api.github.com/users/dwijnand/repos?page=2&per_page=5", "json"),
-///     PathBuf::from("/home/.hubcaps/cache/v1/https/api.github.com/users/dwijnand/repos/d862dcd2d85cebca.json"),
+///     cache_path(&PathBuf::from("/home/.hubcaps/cache"), &key("https://api.github.com/users/dwijnand/repos?page=2&per_page=5")).unwrap(),
+///     PathBuf::from("/home/.hubcaps/cache/v3/0123456789abcdef/https/api.github.com/users/dwijnand/repos/ed57c9edbd9d6730.response"),
 /// );
+/// assert!(cache_path(&PathBuf::from("/home/.hubcaps/cache"), &key("/users/dwijnand/repos")).is_err());
 /// ```
 #[doc(hidden)] // public for doc testing only
-pub fn cache_path<S: AsRef<OsStr>>(dir: &Path, uri: &str, extension: S) -> PathBuf {
-    let uri = uri.parse::<Uri>().expect("Expected a URI");
-    let parts = uri.clone().into_parts();
+pub fn cache_path(dir: &Path, key: &CacheKey) -> Result<PathBuf> {
+    let uri = key.uri.parse::<Uri>().map_err(http::Error::from)?;
+    let (scheme, authority) = match (uri.scheme_str(), uri.authority()) {
+        (Some(scheme), Some(authority)) => (scheme, authority),
+        _ => return Err(url::ParseError::RelativeUrlWithoutBase.into()),
+    };
     let mut path = dir.to_path_buf();
-    path.push("v1");
-    path.push(parts.scheme.expect("no URI scheme").as_str()); // https
-    path.push(parts.authority.expect("no URI authority").as_str()); // api.github.com
+    path.push("v3");
+    path.push(&key.scope); // 0123456789abcdef
+    path.push(scheme); // https
+    path.push(authority.as_str()); // api.github.com
     path.push(Path::new(&uri.path()[1..])); // users/dwijnand/repos
     if let Some(query) = uri.query() {
-        path.push(hash1(query, DefaultHasher::new())); // fa269019d5035d5f
+        path.push(Fnv1a::new().write(query.as_bytes()).hex()); // dbd81f79987a3779
     }
-    path.set_extension(extension); // .json
-    path
-}
-
-fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
-    trace!("reading path: {}", path.as_ref().display());
-    fs::read_to_string(path).map_err(Error::from)
+    path.set_extension("response"); // .response
+    Ok(path)
 }
 
-fn no_read<T, E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> Result<T> {
-    Err(Error::from(io::Error::new(io::ErrorKind::NotFound, error)))
+fn invalid_record(error: &str) -> Error {
+    Error::from(io::Error::new(io::ErrorKind::InvalidData, error))
 }
 
 // Separate to provide a blanket implementation for `T: HttpCache + Clone`
@@ -178,9 +1112,41 @@ where
     }
 }
 
-fn hash1<A: Hash, H: Hasher>(x: A, mut hasher: H) -> String {
-    x.hash(&mut hasher);
-    u64_to_padded_hex(hasher.finish())
+/// 64 bit FNV-1a. Unlike `DefaultHasher`'s, its hashes are specified, so
+/// cache keys and paths stay the same across Rust releases and platforms
+#[derive(Clone, Copy, Debug)]
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Fnv1a(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+        self
+    }
+
+    /// Write a length prefixed value, so consecutive values can't run into
+    /// each other and a missing value differs from an empty one
+    fn write_value(&mut self, value: Option<&[u8]>) -> &mut Self {
+        match value {
+            Some(bytes) => self
+                .write(&(bytes.len() as u64 + 1).to_le_bytes())
+                .write(bytes),
+            None => self.write(&0u64.to_le_bytes()),
+        }
+    }
+
+    fn hex(&self) -> String {
+        u64_to_padded_hex(self.0)
+    }
 }
 
 /// Construct a 0-padded hex string from a u64.
@@ -196,3 +1162,517 @@ fn hash1<A: Hash, H: Hasher>(x: A, mut hasher: H) -> String {
 pub fn u64_to_padded_hex(x: u64) -> String {
     format!("{:016x}", x)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::{CredentialProvider, ProvidedCredential};
+    use crate::transport::scripted::Scripted;
+    use crate::{Credentials, Github, Stream, DEFAULT_HOST};
+    use futures::prelude::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    http::header::HeaderName::from_static(name),
+                    HeaderValue::from_static(value),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hashes_are_stable() {
+        // reference values of the FNV-1a specification
+        for (input, hash) in &[
+            ("", "cbf29ce484222325"),
+            ("a", "af63dc4c8601ec8c"),
+            ("foobar", "85944171f73967e8"),
+        ] {
+            assert_eq!(Fnv1a::new().write(input.as_bytes()).hex(), *hash);
+        }
+        assert_ne!(
+            Fnv1a::new()
+                .write_value(Some(b"ab"))
+                .write_value(None)
+                .hex(),
+            Fnv1a::new()
+                .write_value(Some(b"a"))
+                .write_value(Some(b"b"))
+                .hex()
+        );
+        assert_ne!(
+            Fnv1a::new().write_value(None).hex(),
+            Fnv1a::new().write_value(Some(b"")).hex()
+        );
+    }
+
+    #[test]
+    fn keys_are_scoped_by_credentials_and_media_type() {
+        let uri: Uri = "https://api.github.com/user/repos".parse().unwrap();
+        let key = |pairs| CacheKey::new(&uri, &headers(pairs));
+        let alice = key(&[("authorization", "token alice")]);
+        assert_eq!(alice, key(&[("authorization", "token alice")]));
+        assert_ne!(alice, key(&[("authorization", "token bob")]));
+        assert_ne!(alice, key(&[]));
+        assert_ne!(
+            alice,
+            key(&[
+                ("authorization", "token alice"),
+                ("accept", "application/vnd.github.machine-man-preview+json")
+            ])
+        );
+    }
+
+    #[test]
+    fn keys_are_normalized() {
+        let key = |uri: &str| CacheKey::new(&uri.parse().unwrap(), &HeaderMap::new());
+        let repos = key("https://api.github.com/user/repos?sort=updated&page=2");
+        assert_eq!(
+            repos.uri,
+            "https://api.github.com/user/repos?page=2&sort=updated"
+        );
+        assert_eq!(
+            repos,
+            key("https://api.github.com/user/repos?client_id=id&page=2&client_secret=secret&sort=updated")
+        );
+        assert!(repos.matches("https://api.github.com/user/repos?sort=updated&page=2"));
+        assert!(repos.matches("/user/repos?page=2&sort=updated"));
+        assert!(!repos.matches("/user/repos"));
+        assert!(!repos.matches("/repos?page=2&sort=updated"));
+    }
+
+    #[test]
+    fn vary_fingerprints() {
+        let request = headers(&[("accept", "application/json"), ("x-github-otp", "123")]);
+        let fingerprint = vary_fingerprint("Accept, X-GitHub-OTP", &request).unwrap();
+        assert!(!fingerprint.contains("123"), "values are hashed");
+        assert!(vary_matches(&fingerprint, &request));
+        assert!(!vary_matches(
+            &fingerprint,
+            &headers(&[("accept", "application/json"), ("x-github-otp", "456")])
+        ));
+        assert!(vary_matches("", &HeaderMap::new()));
+        assert_eq!(vary_fingerprint("Accept, *", &request), None);
+    }
+
+    fn key(uri: &str) -> CacheKey {
+        CacheKey {
+            uri: uri.into(),
+            scope: "0123456789abcdef".into(),
+        }
+    }
+
+    fn response(body: &'static str) -> CachedResponse {
+        CachedResponse {
+            status: StatusCode::OK,
+            headers: headers(&[
+                ("etag", "\"abc\""),
+                (
+                    "link",
+                    "<https://api.github.com/user/repos?page=2>; rel=\"next\"",
+                ),
+            ]),
+            body: Bytes::from(body),
+            vary: "accept=0123456789abcdef".into(),
+            stored_at: UNIX_EPOCH + Duration::from_millis(1_600_000_000_000),
+        }
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let record = response("{\n}");
+        let decoded = CachedResponse::from_bytes(&record.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.status, record.status);
+        assert_eq!(decoded.headers, record.headers);
+        assert_eq!(decoded.body, record.body);
+        assert_eq!(decoded.vary, record.vary);
+        assert_eq!(decoded.stored_at, record.stored_at);
+        assert!(CachedResponse::from_bytes(b"{}").is_err());
+    }
+
+    #[tokio::test]
+    async fn discards_outdated_layouts() {
+        let root = std::env::temp_dir().join(format!("hubcaps-cache-old-{}", std::process::id()));
+        for entry in &[
+            "v1/https/api.github.com/user.json",
+            "v2/0123456789abcdef/https/api.github.com/user.json",
+        ] {
+            let entry = root.join(entry);
+            fs::create_dir_all(entry.parent().unwrap()).unwrap();
+            fs::write(&entry, "{}").unwrap();
+        }
+
+        let cache = FileBasedCache::new(&root);
+        assert!(root.join("v1").exists());
+        let key = key("https://api.github.com/user");
+        assert!(cache.lookup(&key).await.unwrap().is_none());
+        assert!(!root.join("v1").exists());
+        assert!(!root.join("v2").exists());
+
+        cache.store(&key, response("{}")).await.unwrap();
+        let cached = cache.lookup(&key).await.unwrap().unwrap();
+        assert_eq!(cached.etag().unwrap(), "\"abc\"");
+        assert_eq!(cached.body, "{}");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn memory_cache_evicts_least_recently_used() {
+        let cache = MemoryCache::new().max_entries(2);
+        let (a, b, c) = (key("/a"), key("/b"), key("/c"));
+        for key in &[&a, &b] {
+            cache.store(key, response("{}")).await.unwrap();
+        }
+        assert!(cache.lookup(&a).await.unwrap().is_some());
+        cache.store(&c, response("{}")).await.unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(
+            cache.lookup(&b).await.unwrap().is_none(),
+            "b was least recently used"
+        );
+        assert!(cache.lookup(&a).await.unwrap().is_some());
+        assert!(cache.lookup(&c).await.unwrap().is_some());
+
+        let size = response("1234").size();
+        let cache = MemoryCache::new().max_bytes(size + 2);
+        cache.store(&a, response("1234")).await.unwrap();
+        cache.store(&b, response("5678")).await.unwrap();
+        assert_eq!(cache.len(), 1);
+        cache.store(&c, response("too big to cache")).await.unwrap();
+        assert!(cache.lookup(&c).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn memory_cache_ttl_and_stats() {
+        let cache = MemoryCache::new().ttl(Duration::from_millis(0));
+        let a = key("/a");
+        cache.store(&a, response("{}")).await.unwrap();
+        assert!(cache.lookup(&a).await.unwrap().is_none());
+        assert!(cache.is_empty());
+
+        let cache = MemoryCache::new();
+        cache.store(&a, response("[1, 2]")).await.unwrap();
+        assert!(cache.lookup(&key("/b")).await.unwrap().is_none());
+        let cached = cache.lookup(&a).await.unwrap().unwrap();
+        cache.revalidated(&a, &cached).await.unwrap();
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                revalidations: 1,
+                bytes_saved: 6,
+                evictions: 0,
+            }
+        );
+
+        let other = CacheKey {
+            uri: "/a".into(),
+            scope: "fedcba9876543210".into(),
+        };
+        cache.store(&other, response("{}")).await.unwrap();
+        cache.invalidate("/a").await.unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn file_cache_gc_and_invalidation() {
+        let root = std::env::temp_dir().join(format!("hubcaps-cache-gc-{}", std::process::id()));
+        let size = response("0123456789").to_bytes().unwrap().len() as u64;
+        let cache = FileBasedCache::new(&root).max_bytes(size * 5 / 2);
+        let (a, b, c) = (
+            key("https://api.github.com/a"),
+            key("https://api.github.com/b"),
+            key("https://api.github.com/c"),
+        );
+        cache.store(&a, response("0123456789")).await.unwrap();
+        cache.store(&b, response("0123456789")).await.unwrap();
+        // make sure a is older than b, whatever the file system's timestamp resolution
+        for (key, age) in &[(&a, 120), (&b, 60)] {
+            let file = fs::OpenOptions::new()
+                .append(true)
+                .open(cache_path(&root, key).unwrap())
+                .unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(*age))
+                .unwrap();
+        }
+        // serving a response marks it as recently used
+        assert!(cache.lookup(&a).await.unwrap().is_some());
+        cache.store(&c, response("0123456789")).await.unwrap();
+        assert!(
+            cache.lookup(&b).await.unwrap().is_none(),
+            "b was least recently used"
+        );
+        assert!(cache.lookup(&a).await.unwrap().is_some());
+        assert_eq!(cache.stats().evictions, 1);
+
+        cache.invalidate("https://api.github.com/a").await.unwrap();
+        assert!(cache.lookup(&a).await.unwrap().is_none());
+        assert!(cache.lookup(&c).await.unwrap().is_some());
+        cache.clear().await.unwrap();
+        assert!(cache.lookup(&c).await.unwrap().is_none());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_cache_invalidates_relative_uris() {
+        let root = std::env::temp_dir().join(format!("hubcaps-cache-rel-{}", std::process::id()));
+        let cache = FileBasedCache::new(&root);
+        let repo = key("https://api.github.com/repos/o/r");
+        let other_scope = CacheKey {
+            uri: repo.uri.clone(),
+            scope: "fedcba9876543210".into(),
+        };
+        let other_host = key("https://github.example.com/repos/o/r");
+        let issues = key("https://api.github.com/repos/o/r/issues");
+        for key in &[&repo, &other_scope, &other_host, &issues] {
+            cache.store(key, response("{}")).await.unwrap();
+        }
+
+        cache.invalidate("/repos/o/r").await.unwrap();
+        for key in &[&repo, &other_scope, &other_host] {
+            assert!(cache.lookup(key).await.unwrap().is_none());
+        }
+        assert!(cache.lookup(&issues).await.unwrap().is_some());
+        assert!(cache.lookup(&key("/repos/o/r")).await.is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_cache_invalidation_with_per_page() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, "{}"),
+            (StatusCode::OK, "[]"),
+            (StatusCode::OK, "{}"),
+            (StatusCode::OK, "[]"),
+        ])
+        .header_all("etag", "\"a\"");
+        let root = std::env::temp_dir().join(format!("hubcaps-cache-pp-{}", std::process::id()));
+        let cache = FileBasedCache::new(&root);
+        let github = transport
+            .builder()
+            .credentials(Credentials::Client("id".into(), "secret".into()))
+            .per_page(100)
+            .http_cache(Box::new(cache.clone()))
+            .build()
+            .unwrap();
+        let fetch = || async {
+            github.get::<serde_json::Value>("/repos/o/r").await.unwrap();
+            github
+                .get_stream::<u32>("/repos/o/r/issues")
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+        };
+
+        fetch().await;
+        cache.invalidate("/repos/o/r").await.unwrap();
+        cache
+            .invalidate("https://api.github.com/repos/o/r/issues?per_page=100")
+            .await
+            .unwrap();
+        fetch().await;
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 4);
+        for request in &requests[2..] {
+            assert!(
+                request.headers().get(IF_NONE_MATCH).is_none(),
+                "{} was invalidated",
+                request.uri()
+            );
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn conditional_requests() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, r#"{"login": "octocat"}"#),
+            (StatusCode::NOT_MODIFIED, ""),
+            (StatusCode::OK, r#"{"login": "other"}"#),
+        ])
+        .header(0, "etag", "\"abc\"");
+        let cache_dir = std::env::temp_dir().join(format!("hubcaps-etags-{}", std::process::id()));
+        let cache = FileBasedCache::new(&cache_dir);
+        let github = Github::custom(
+            DEFAULT_HOST,
+            "test-agent",
+            None,
+            transport.clone(),
+            Box::new(cache.clone()),
+        );
+
+        for _ in 0..2 {
+            let user: serde_json::Value = github.get("/user").await.unwrap();
+            assert_eq!(user["login"], "octocat");
+        }
+        // a client with other credentials doesn't see the cached response
+        let mut other = github.clone();
+        other.set_credentials(Credentials::Token("other".into()));
+        other.get::<serde_json::Value>("/user").await.unwrap();
+
+        let requests = transport.requests();
+        assert!(requests[0]
+            .headers()
+            .get(http::header::IF_NONE_MATCH)
+            .is_none());
+        assert_eq!(
+            requests[1].headers()[http::header::IF_NONE_MATCH],
+            "\"abc\""
+        );
+        assert!(requests[2]
+            .headers()
+            .get(http::header::IF_NONE_MATCH)
+            .is_none());
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                revalidations: 1,
+                bytes_saved: 20,
+                evictions: 0,
+            }
+        );
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    /// Provides a new token for the same user on every request
+    #[derive(Debug, Default)]
+    struct Rotating(AtomicU64);
+
+    impl CredentialProvider for Rotating {
+        fn credential(&self) -> crate::Future<ProvidedCredential> {
+            let call = self.0.fetch_add(1, Ordering::SeqCst);
+            Box::pin(futures::future::ok(
+                ProvidedCredential::token(format!("secret-{}", call))
+                    .expires_at(SystemTime::now())
+                    .identity("octocat"),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn rotated_credentials_share_responses() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, r#"{"login": "octocat"}"#),
+            (StatusCode::NOT_MODIFIED, ""),
+        ])
+        .header(0, "etag", "\"abc\"");
+        let github = transport
+            .builder()
+            .credentials(Credentials::provider(Rotating::default()))
+            .http_cache(Box::new(MemoryCache::new()))
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            let user: serde_json::Value = github.get("/user").await.unwrap();
+            assert_eq!(user["login"], "octocat");
+        }
+        let requests = transport.requests();
+        assert_ne!(
+            requests[0].headers()[AUTHORIZATION],
+            requests[1].headers()[AUTHORIZATION]
+        );
+        assert_eq!(requests[1].headers()[IF_NONE_MATCH], "\"abc\"");
+    }
+
+    #[tokio::test]
+    async fn cache_modes() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, "[1, 2]"),
+            (StatusCode::OK, "[3]"),
+            (StatusCode::NOT_MODIFIED, ""),
+        ])
+        .header(0, "etag", "\"a\"")
+        .header(1, "etag", "\"b\"")
+        .header(
+            0,
+            "link",
+            r#"<https://api.github.com/repos/o/r/things?page=2>; rel="next""#,
+        );
+        let cache = MemoryCache::new();
+        let mut github = transport
+            .builder()
+            .http_cache(Box::new(cache.clone()))
+            .build()
+            .unwrap();
+        let things = |github: &Github| -> Stream<u32> { github.get_stream("/repos/o/r/things") };
+        assert_eq!(
+            things(&github).try_collect::<Vec<_>>().await.unwrap(),
+            vec![1, 2, 3]
+        );
+
+        github.set_cache_mode(CacheMode::Offline);
+        assert_eq!(
+            things(&github).try_collect::<Vec<_>>().await.unwrap(),
+            vec![1, 2, 3]
+        );
+        match github
+            .get::<serde_json::Value>("/user")
+            .await
+            .map_err(Error::into_kind)
+        {
+            Err(ErrorKind::NotCached { uri }) => assert_eq!(uri, "https://api.github.com/user"),
+            otherwise => panic!("expected an uncached response, got {:?}", otherwise),
+        }
+        let mut client = github.clone();
+        client.set_credentials(Credentials::Client("id".into(), "secret".into()));
+        match client
+            .get::<serde_json::Value>("/user")
+            .await
+            .map_err(Error::into_kind)
+        {
+            Err(ErrorKind::NotCached { uri }) => assert_eq!(uri, "https://api.github.com/user"),
+            otherwise => panic!("expected an uncached response, got {:?}", otherwise),
+        }
+        assert_eq!(transport.requests().len(), 2);
+
+        github.set_cache_mode(CacheMode::StaleWhileRevalidate);
+        let page: Vec<u32> = github.get("/repos/o/r/things").await.unwrap();
+        assert_eq!(page, vec![1, 2]);
+        while transport.requests().len() < 3 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            transport.requests()[2].headers()[http::header::IF_NONE_MATCH],
+            "\"a\""
+        );
+        while cache.stats().revalidations < 1 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[test]
+    fn stale_while_revalidate_without_a_runtime() {
+        let transport = Scripted::new(vec![(StatusCode::OK, "[1]"), (StatusCode::OK, "[1, 2]")])
+            .header_all("etag", "\"a\"");
+        let github = transport
+            .builder()
+            .http_cache(Box::new(MemoryCache::new()))
+            .cache_mode(CacheMode::StaleWhileRevalidate)
+            .build()
+            .unwrap();
+        // the scripted transport and memory cache answer straight away
+        let get = |github: &Github| {
+            let mut request = github.get::<Vec<u32>>("/things");
+            let waker = futures::task::noop_waker();
+            match request
+                .as_mut()
+                .poll(&mut std::task::Context::from_waker(&waker))
+            {
+                std::task::Poll::Ready(result) => result.unwrap(),
+                std::task::Poll::Pending => panic!("request didn't complete"),
+            }
+        };
+        assert_eq!(get(&github), vec![1]);
+        // with nothing to spawn a revalidation on, it happens before answering
+        assert_eq!(get(&github), vec![1, 2]);
+        assert_eq!(transport.requests()[1].headers()[IF_NONE_MATCH], "\"a\"");
+    }
+}
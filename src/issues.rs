@@ -7,14 +7,16 @@ use url::form_urlencoded;
 
 use crate::comments::Comments;
 use crate::labels::Label;
+use crate::pagination::Page;
 use crate::users::User;
 use crate::utils::{percent_encode, PATH_SEGMENT};
-use crate::{Future, Github, SortDirection, Stream};
+use crate::{Future, Github, MediaType, SortDirection, Stream};
 
 /// enum representation of github pull and issue state
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum State {
     /// Only open issues
+    #[default]
     Open,
     /// Only closed issues
     Closed,
@@ -33,16 +35,11 @@ impl fmt::Display for State {
     }
 }
 
-impl Default for State {
-    fn default() -> State {
-        State::Open
-    }
-}
-
 /// Sort options available for github issues
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum Sort {
     /// sort by creation time of issue
+    #[default]
     Created,
     /// sort by the last time issue was updated
     Updated,
@@ -61,12 +58,6 @@ impl fmt::Display for Sort {
     }
 }
 
-impl Default for Sort {
-    fn default() -> Sort {
-        Sort::Created
-    }
-}
-
 /// Provides access to assignee operations available for an individual issue
 pub struct IssueAssignees {
     github: Github,
@@ -189,6 +180,12 @@ impl IssueRef {
         self.github.get(&self.path(""))
     }
 
+    /// Request an issue's information, with its body in the representations
+    /// selected by `media`, e.g. `MediaType::Full` to include `body_html`
+    pub fn get_media(&self, media: MediaType) -> Future<Issue> {
+        self.github.get_media(&self.path(""), media)
+    }
+
     fn path(&self, more: &str) -> String {
         format!(
             "/repos/{}/{}/issues/{}{}",
@@ -296,6 +293,24 @@ impl Issues {
         }
         self.github.get_stream(&uri.join("?"))
     }
+
+    /// provides a stream over each page of issues
+    pub fn pages(&self, options: &IssueListOptions) -> Stream<Page<Issue>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page_stream(&uri.join("?"))
+    }
+
+    /// fetch a single page of issues, starting at page 1
+    pub fn page(&self, options: &IssueListOptions, number: u32) -> Future<Page<Issue>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page(&uri.join("?"), number)
+    }
 }
 
 // representations
@@ -468,6 +483,10 @@ pub struct Issue {
     pub state: String,
     pub title: String,
     pub body: Option<String>,
+    /// present when requested with `MediaType::Html` or `MediaType::Full`
+    pub body_html: Option<String>,
+    /// present when requested with `MediaType::Text` or `MediaType::Full`
+    pub body_text: Option<String>,
     pub user: User,
     pub labels: Vec<Label>,
     pub assignee: Option<User>,
@@ -1,6 +1,7 @@
 //! Labels interface
 use serde::{Deserialize, Serialize};
 
+use crate::pagination::Page;
 use crate::{Future, Github, Stream};
 
 pub struct Labels {
@@ -48,6 +49,16 @@ impl Labels {
     pub fn iter(&self) -> Stream<Label> {
         self.github.get_stream(&self.path(""))
     }
+
+    /// provides a stream over each page of this repo's labels
+    pub fn pages(&self) -> Stream<Page<Label>> {
+        self.github.get_page_stream(&self.path(""))
+    }
+
+    /// fetch a single page of this repo's labels, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<Label>> {
+        self.github.get_page(&self.path(""), number)
+    }
 }
 
 // representations
@@ -1,8 +1,9 @@
 //! Organization Membership interface
 use serde::Deserialize;
 
+use crate::pagination::Page;
 use crate::users::User;
-use crate::{Github, Stream};
+use crate::{Future, Github, Stream};
 
 /// Provides access to membership operations available for an individual organization
 pub struct OrgMembership {
@@ -30,6 +31,18 @@ impl OrgMembership {
         self.github
             .get_stream(&format!("/orgs/{}/invitations", self.org))
     }
+
+    /// Return a stream of each page of invitations for this organization
+    pub fn invitation_pages(&self) -> Stream<Page<Invitation>> {
+        self.github
+            .get_page_stream(&format!("/orgs/{}/invitations", self.org))
+    }
+
+    /// Fetch a single page of invitations for this organization, starting at page 1
+    pub fn invitations_page(&self, number: u32) -> Future<Page<Invitation>> {
+        self.github
+            .get_page(&format!("/orgs/{}/invitations", self.org), number)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,219 @@
+//! Request and response middleware
+//!
+//! Every request a `Github` client sends, including retries and the follow-up
+//! page requests of paginated listings, passes through its chain of
+//! `Middleware` before reaching the `HttpTransport`. Middleware added first
+//! sees requests first and responses last.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use bytes::Bytes;
+//! use http::{Request, Response};
+//! use hubcaps::middleware::{Middleware, RequestInfo};
+//! use hubcaps::{Github, Result};
+//!
+//! #[derive(Debug)]
+//! struct Audit;
+//!
+//! impl Middleware for Audit {
+//!     fn before_send(&self, request: &mut Request<Vec<u8>>) -> Result<()> {
+//!         request
+//!             .headers_mut()
+//!             .insert("x-audit", "hubcaps".parse().unwrap());
+//!         Ok(())
+//!     }
+//!
+//!     fn after_receive(&self, request: &RequestInfo, response: &mut Response<Bytes>) -> Result<()> {
+//!         println!(
+//!             "{} {} -> {} in {:?} ({:?})",
+//!             request.method,
+//!             request.uri,
+//!             response.status(),
+//!             request.elapsed,
+//!             response.headers().get("x-github-request-id")
+//!         );
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # fn main() -> Result<()> {
+//! let mut github = Github::new("user-agent-name", None)?;
+//! github.add_middleware(Audit);
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http::{HeaderMap, Method, Request, Response, Uri};
+
+use crate::transport::BoxedTransport;
+use crate::Future;
+
+/// A boxed middleware, shared between clones of a `Github` client
+pub type BoxedMiddleware = Arc<dyn Middleware>;
+
+/// Observes or alters requests and their responses
+pub trait Middleware: fmt::Debug + Send + Sync + 'static {
+    /// Called before a request is sent. Returning an error aborts the request
+    fn before_send(&self, _request: &mut Request<Vec<u8>>) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// Called once a response is received. Returning an error fails the request
+    fn after_receive(
+        &self,
+        _request: &RequestInfo,
+        _response: &mut Response<Bytes>,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// Pass a request on to the rest of the chain. The default implementation
+    /// calls `before_send` and `after_receive` around `next`. Override this to
+    /// take full control, e.g. to answer requests without sending them
+    fn handle(
+        self: Arc<Self>,
+        mut request: Request<Vec<u8>>,
+        next: Next,
+    ) -> Future<Response<Bytes>> {
+        Box::pin(async move {
+            self.before_send(&mut request)?;
+            let mut info = RequestInfo {
+                method: request.method().clone(),
+                uri: request.uri().clone(),
+                headers: request.headers().clone(),
+                body_size: request.body().len(),
+                elapsed: Duration::default(),
+            };
+            let start = Instant::now();
+            let mut response = next.run(request).await?;
+            info.elapsed = start.elapsed();
+            self.after_receive(&info, &mut response)?;
+            Ok(response)
+        })
+    }
+}
+
+/// A summary of a sent request, handed to `Middleware::after_receive`
+#[derive(Clone, Debug)]
+pub struct RequestInfo {
+    pub method: Method,
+    pub uri: Uri,
+    /// request headers, as modified by this and all previous middleware
+    pub headers: HeaderMap,
+    /// size of the request body in bytes
+    pub body_size: usize,
+    /// time spent waiting on the rest of the chain for a response
+    pub elapsed: Duration,
+}
+
+/// The remainder of a middleware chain, ending with the transport
+pub struct Next {
+    middleware: Arc<Vec<BoxedMiddleware>>,
+    index: usize,
+    transport: BoxedTransport,
+}
+
+impl Next {
+    pub(crate) fn new(middleware: Arc<Vec<BoxedMiddleware>>, transport: BoxedTransport) -> Self {
+        Next {
+            middleware,
+            index: 0,
+            transport,
+        }
+    }
+
+    /// Send a request through the rest of the chain
+    pub fn run(self, request: Request<Vec<u8>>) -> Future<Response<Bytes>> {
+        match self.middleware.get(self.index).cloned() {
+            Some(middleware) => middleware.handle(
+                request,
+                Next {
+                    index: self.index + 1,
+                    ..self
+                },
+            ),
+            None => self.transport.send(request),
+        }
+    }
+}
+
+impl fmt::Debug for Next {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("middleware", &&self.middleware[self.index..])
+            .field("transport", &self.transport)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use crate::Result;
+    use bytes::Bytes;
+    use http::header::HeaderValue;
+    use http::StatusCode;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the order in which it sees requests and responses
+    #[derive(Debug)]
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Middleware for Recorder {
+        fn before_send(&self, request: &mut http::Request<Vec<u8>>) -> Result<()> {
+            self.log.lock().unwrap().push(format!("send {}", self.name));
+            request
+                .headers_mut()
+                .insert("x-middleware", HeaderValue::from_static(self.name));
+            Ok(())
+        }
+
+        fn after_receive(
+            &self,
+            request: &RequestInfo,
+            response: &mut http::Response<Bytes>,
+        ) -> Result<()> {
+            self.log.lock().unwrap().push(format!(
+                "receive {} {} {}",
+                self.name,
+                request.headers["x-middleware"].to_str().unwrap(),
+                response.status().as_u16()
+            ));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_chain() {
+        let transport = Scripted::new(vec![(StatusCode::OK, r#"{"login": "octocat"}"#)]);
+        let mut github = transport.github(None);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        for name in &["outer", "inner"] {
+            github.add_middleware(Recorder {
+                name,
+                log: log.clone(),
+            });
+        }
+
+        github.get::<serde_json::Value>("/user").await.unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "send outer",
+                "send inner",
+                "receive inner inner 200",
+                "receive outer outer 200"
+            ]
+        );
+        assert_eq!(transport.requests()[0].headers()["x-middleware"], "inner");
+    }
+}
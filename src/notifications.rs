@@ -264,6 +264,7 @@ impl ThreadListOptionsBuilder {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct Subject {
     title: String,
@@ -0,0 +1,614 @@
+//! OAuth user authorization
+//!
+//! Obtain user access tokens for an OAuth or GitHub App, either with the
+//! [device flow](https://docs.github.com/en/apps/oauth-apps/building-oauth-apps/authorizing-oauth-apps#device-flow),
+//! suited to command line tools, or the
+//! [web application flow](https://docs.github.com/en/apps/oauth-apps/building-oauth-apps/authorizing-oauth-apps#web-application-flow).
+//!
+//! ```no_run
+//! use hubcaps::oauth::OAuth;
+//! use hubcaps::Github;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let oauth = OAuth::new("client-id", None)?;
+//! let code = oauth.device_code(&["repo"]).await?;
+//! println!("Enter {} at {}", code.user_code, code.verification_uri);
+//! let token = oauth.poll_device_token(&code).await?;
+//! let github = Github::new("user-agent-name", token.credentials())?;
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures::future;
+use http::header::{ACCEPT, CONTENT_TYPE, USER_AGENT};
+use http::{Method, Request, StatusCode};
+use reqwest::{Client, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::form_urlencoded;
+
+use crate::errors::RequestContext;
+use crate::transport::{BoxedTransport, HttpTransport};
+use crate::{Credentials, ErrorKind, Future, Result};
+
+const DEFAULT_HOST: &str = "https://github.com";
+const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+// GitHub asks clients polling too quickly to wait this much longer between polls
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+/// Authorizes users of an OAuth or GitHub App
+#[derive(Clone)]
+pub struct OAuth {
+    host: String,
+    client_id: String,
+    client_secret: Option<String>,
+    transport: BoxedTransport,
+}
+
+impl fmt::Debug for OAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth")
+            .field("host", &self.host)
+            .field("client_id", &self.client_id)
+            .field(
+                "client_secret",
+                &self.client_secret.as_ref().map(|_| "*****"),
+            )
+            .finish()
+    }
+}
+
+impl OAuth {
+    /// The device flow only requires a client id, the web application flow
+    /// also requires the client secret
+    pub fn new<I, S>(client_id: I, client_secret: S) -> Result<Self>
+    where
+        I: Into<String>,
+        S: Into<Option<String>>,
+    {
+        Ok(Self::custom(
+            DEFAULT_HOST,
+            client_id,
+            client_secret,
+            Client::builder().build()?,
+        ))
+    }
+
+    /// Authorize users against a GitHub Enterprise host or a local stand-in
+    /// of `https://github.com/login`, sending requests through a custom
+    /// `HttpTransport`
+    pub fn custom<H, I, S, T>(host: H, client_id: I, client_secret: S, transport: T) -> Self
+    where
+        H: Into<String>,
+        I: Into<String>,
+        S: Into<Option<String>>,
+        T: HttpTransport + 'static,
+    {
+        Self {
+            host: host.into().trim_end_matches('/').into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            transport: Arc::new(transport),
+        }
+    }
+
+    /// Request a device and user code, the first step of the device flow.
+    /// Show the user code and verification uri to the user, then wait for
+    /// them to authorize the app with `poll_device_token`
+    pub fn device_code(&self, scopes: &[&str]) -> Future<DeviceCode> {
+        self.post(
+            "/login/device/code",
+            &[("client_id", &self.client_id), ("scope", &scopes.join(" "))],
+        )
+    }
+
+    /// Poll for the access token of a device code until the user authorized
+    /// or denied the app, or the code expired. Polls are spaced by the
+    /// code's interval, which grows whenever GitHub asks to slow down
+    pub fn poll_device_token(&self, code: &DeviceCode) -> Future<AccessToken> {
+        let oauth = self.clone();
+        let device_code = code.device_code.clone();
+        let mut interval = Duration::from_secs(code.interval);
+        let expires = Instant::now() + Duration::from_secs(code.expires_in);
+        Box::pin(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let response: TokenResponse = oauth
+                    .post(
+                        "/login/oauth/access_token",
+                        &[
+                            ("client_id", &oauth.client_id),
+                            ("device_code", &device_code),
+                            ("grant_type", DEVICE_GRANT_TYPE),
+                        ],
+                    )
+                    .await?;
+                match response {
+                    TokenResponse::Token(token) => return Ok(token),
+                    TokenResponse::Error(err) => match err.error.as_str() {
+                        "authorization_pending" if Instant::now() < expires => (),
+                        "slow_down" if Instant::now() < expires => {
+                            interval = err
+                                .interval
+                                .map(Duration::from_secs)
+                                .unwrap_or(interval + SLOW_DOWN_INCREMENT);
+                        }
+                        _ => return Err(err.into()),
+                    },
+                }
+            }
+        })
+    }
+
+    /// The url to send users to in order to authorize the app, the first step
+    /// of the web application flow. GitHub redirects them back with a code to
+    /// exchange for a token with `exchange_code`
+    pub fn authorize_url(&self, options: &AuthorizeOptions) -> Result<Url> {
+        let mut url = Url::parse(&format!("{}/login/oauth/authorize", self.host))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("client_id", &self.client_id);
+            if let Some(redirect_uri) = &options.redirect_uri {
+                query.append_pair("redirect_uri", redirect_uri);
+            }
+            if !options.scopes.is_empty() {
+                query.append_pair("scope", &options.scopes.join(" "));
+            }
+            if let Some(state) = &options.state {
+                query.append_pair("state", state);
+            }
+            if let Some(login) = &options.login {
+                query.append_pair("login", login);
+            }
+            if let Some(allow_signup) = options.allow_signup {
+                query.append_pair("allow_signup", &allow_signup.to_string());
+            }
+        }
+        Ok(url)
+    }
+
+    /// Exchange the code GitHub redirected a user back with for an access token
+    pub fn exchange_code(&self, code: &str) -> Future<AccessToken> {
+        let secret = match self.client_secret() {
+            Ok(secret) => secret,
+            Err(err) => return Box::pin(future::err(err)),
+        };
+        self.token(&[
+            ("client_id", &self.client_id),
+            ("client_secret", secret),
+            ("code", code),
+        ])
+    }
+
+    /// Exchange the refresh token of an expiring user-to-server token for a
+    /// new access token
+    pub fn refresh(&self, refresh_token: &str) -> Future<AccessToken> {
+        let secret = match self.client_secret() {
+            Ok(secret) => secret,
+            Err(err) => return Box::pin(future::err(err)),
+        };
+        self.token(&[
+            ("client_id", &self.client_id),
+            ("client_secret", secret),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ])
+    }
+
+    fn client_secret(&self) -> Result<&str> {
+        self.client_secret
+            .as_deref()
+            .ok_or_else(|| ErrorKind::MissingClientSecret.into())
+    }
+
+    fn token(&self, form: &[(&str, &str)]) -> Future<AccessToken> {
+        let response = self.post::<TokenResponse>("/login/oauth/access_token", form);
+        Box::pin(async move {
+            match response.await? {
+                TokenResponse::Token(token) => Ok(token),
+                TokenResponse::Error(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn post<D>(&self, path: &str, form: &[(&str, &str)]) -> Future<D>
+    where
+        D: DeserializeOwned + Send + 'static,
+    {
+        let url = format!("{}{}", self.host, path);
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&url)
+            .header(USER_AGENT, concat!("hubcaps/", env!("CARGO_PKG_VERSION")))
+            .header(ACCEPT, "application/json")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body.into_bytes());
+        let transport = self.transport.clone();
+        Box::pin(async move {
+            let response = transport.send(request?).await?;
+            let context = RequestContext::new(&Method::POST, &url, Some(&response));
+            decode(response).map_err(|err| err.with_context(context))
+        })
+    }
+}
+
+fn decode<D>(response: http::Response<Bytes>) -> Result<D>
+where
+    D: DeserializeOwned,
+{
+    let (parts, body) = response.into_parts();
+    if parts.status != StatusCode::OK {
+        return Err(ErrorKind::UnexpectedResponse {
+            code: parts.status,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        }
+        .into());
+    }
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Options for the authorize url of the web application flow
+#[derive(Debug, Default)]
+pub struct AuthorizeOptions {
+    redirect_uri: Option<String>,
+    scopes: Vec<String>,
+    state: Option<String>,
+    login: Option<String>,
+    allow_signup: Option<bool>,
+}
+
+impl AuthorizeOptions {
+    pub fn builder() -> AuthorizeOptionsBuilder {
+        AuthorizeOptionsBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct AuthorizeOptionsBuilder(AuthorizeOptions);
+
+impl AuthorizeOptionsBuilder {
+    /// where GitHub sends users after they authorized the app. defaults to the
+    /// app's callback url
+    pub fn redirect_uri<R>(&mut self, redirect_uri: R) -> &mut Self
+    where
+        R: Into<String>,
+    {
+        self.0.redirect_uri = Some(redirect_uri.into());
+        self
+    }
+
+    pub fn scopes<S>(&mut self, scopes: Vec<S>) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.0.scopes = scopes.into_iter().map(|s| s.into()).collect::<Vec<_>>();
+        self
+    }
+
+    /// an unguessable value, echoed back with the code, to protect against
+    /// cross-site request forgery
+    pub fn state<S>(&mut self, state: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.0.state = Some(state.into());
+        self
+    }
+
+    /// suggest an account to sign in with
+    pub fn login<L>(&mut self, login: L) -> &mut Self
+    where
+        L: Into<String>,
+    {
+        self.0.login = Some(login.into());
+        self
+    }
+
+    pub fn allow_signup(&mut self, allow_signup: bool) -> &mut Self {
+        self.0.allow_signup = Some(allow_signup);
+        self
+    }
+
+    pub fn build(&self) -> AuthorizeOptions {
+        AuthorizeOptions {
+            redirect_uri: self.0.redirect_uri.clone(),
+            scopes: self.0.scopes.clone(),
+            state: self.0.state.clone(),
+            login: self.0.login.clone(),
+            allow_signup: self.0.allow_signup,
+        }
+    }
+}
+
+// representations
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct DeviceCode {
+    pub device_code: String,
+    /// the code users enter at the verification uri
+    pub user_code: String,
+    pub verification_uri: String,
+    /// seconds until the codes expire
+    pub expires_in: u64,
+    /// minimum seconds between polls for the access token
+    pub interval: u64,
+}
+
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct AccessToken {
+    pub access_token: String,
+    pub token_type: String,
+    /// comma separated scopes granted to the token
+    #[serde(default)]
+    pub scope: String,
+    /// seconds until an expiring user-to-server token expires
+    pub expires_in: Option<u64>,
+    pub refresh_token: Option<String>,
+    /// seconds until the refresh token expires
+    pub refresh_token_expires_in: Option<u64>,
+}
+
+impl AccessToken {
+    /// Credentials authenticating with this token, for `Github::new`
+    pub fn credentials(&self) -> Credentials {
+        Credentials::Token(self.access_token.clone())
+    }
+
+    pub fn scopes(&self) -> Vec<&str> {
+        self.scope
+            .split(',')
+            .map(str::trim)
+            .filter(|scope| !scope.is_empty())
+            .collect()
+    }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessToken")
+            .field("access_token", &"*****")
+            .field("token_type", &self.token_type)
+            .field("scope", &self.scope)
+            .field("expires_in", &self.expires_in)
+            .field(
+                "refresh_token",
+                &self.refresh_token.as_ref().map(|_| "*****"),
+            )
+            .field("refresh_token_expires_in", &self.refresh_token_expires_in)
+            .finish()
+    }
+}
+
+/// GitHub answers token requests with a 200 whether or not they succeed
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TokenResponse {
+    Error(OAuthError),
+    Token(AccessToken),
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthError {
+    error: String,
+    error_description: Option<String>,
+    /// the new minimum interval between polls, sent along with `slow_down`
+    interval: Option<u64>,
+}
+
+impl From<OAuthError> for crate::Error {
+    fn from(err: OAuthError) -> Self {
+        ErrorKind::OAuth {
+            error: err.error,
+            description: err.error_description,
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use futures::future;
+
+    use super::*;
+
+    /// A stand-in for `https://github.com/login`, answering each path with a
+    /// queue of bodies
+    #[derive(Debug, Default)]
+    struct Login {
+        responses: Mutex<HashMap<&'static str, Vec<&'static str>>>,
+        requests: Mutex<Vec<(String, String)>>,
+    }
+
+    impl Login {
+        fn new(responses: Vec<(&'static str, &'static str)>) -> Arc<Self> {
+            let login = Login::default();
+            for (path, body) in responses {
+                login
+                    .responses
+                    .lock()
+                    .unwrap()
+                    .entry(path)
+                    .or_default()
+                    .push(body);
+            }
+            Arc::new(login)
+        }
+    }
+
+    impl HttpTransport for Login {
+        fn send(&self, request: Request<Vec<u8>>) -> Future<http::Response<Bytes>> {
+            let path = request.uri().path().to_string();
+            let body = String::from_utf8(request.body().clone()).unwrap();
+            self.requests.lock().unwrap().push((path.clone(), body));
+            let mut responses = self.responses.lock().unwrap();
+            let response = match responses.get_mut(path.as_str()) {
+                Some(bodies) if !bodies.is_empty() => http::Response::builder()
+                    .status(StatusCode::OK)
+                    .body(Bytes::from_static(bodies.remove(0).as_bytes())),
+                _ => http::Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Bytes::from_static(b"<html>Not Found</html>")),
+            };
+            Box::pin(future::ok(response.unwrap()))
+        }
+    }
+
+    fn oauth(login: &Arc<Login>) -> OAuth {
+        OAuth::custom(
+            "http://localhost:8080/",
+            "client-id",
+            Some("client-secret".to_string()),
+            login.clone(),
+        )
+    }
+
+    #[tokio::test]
+    async fn device_flow() {
+        let login = Login::new(vec![
+            (
+                "/login/device/code",
+                r#"{"device_code": "dc", "user_code": "WDJB-MJHT", "verification_uri": "https://github.com/login/device", "expires_in": 900, "interval": 0}"#,
+            ),
+            (
+                "/login/oauth/access_token",
+                r#"{"error": "authorization_pending"}"#,
+            ),
+            (
+                "/login/oauth/access_token",
+                r#"{"error": "slow_down", "interval": 0}"#,
+            ),
+            (
+                "/login/oauth/access_token",
+                r#"{"access_token": "gho_abc", "token_type": "bearer", "scope": "repo,gist"}"#,
+            ),
+        ]);
+        let oauth = oauth(&login);
+        let code = oauth.device_code(&["repo", "gist"]).await.unwrap();
+        assert_eq!(code.user_code, "WDJB-MJHT");
+        let token = oauth.poll_device_token(&code).await.unwrap();
+        assert_eq!(token.scopes(), vec!["repo", "gist"]);
+        match token.credentials() {
+            Credentials::Token(token) => assert_eq!(token, "gho_abc"),
+            otherwise => panic!("expected a token, got {:?}", otherwise),
+        }
+
+        let requests = login.requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].1, "client_id=client-id&scope=repo+gist");
+        assert_eq!(
+            requests[1].1,
+            "client_id=client-id&device_code=dc&grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code"
+        );
+    }
+
+    #[tokio::test]
+    async fn device_flow_denied() {
+        let login = Login::new(vec![(
+            "/login/oauth/access_token",
+            r#"{"error": "access_denied", "error_description": "The user has denied your application access."}"#,
+        )]);
+        let code = DeviceCode {
+            device_code: "dc".into(),
+            user_code: "WDJB-MJHT".into(),
+            verification_uri: "https://github.com/login/device".into(),
+            expires_in: 900,
+            interval: 0,
+        };
+        match oauth(&login)
+            .poll_device_token(&code)
+            .await
+            .map_err(crate::Error::into_kind)
+        {
+            Err(ErrorKind::OAuth { error, description }) => {
+                assert_eq!(error, "access_denied");
+                assert!(description.unwrap().contains("denied"));
+            }
+            otherwise => panic!("expected an oauth error, got {:?}", otherwise),
+        }
+    }
+
+    #[tokio::test]
+    async fn web_flow() {
+        let login = Login::new(vec![
+            (
+                "/login/oauth/access_token",
+                r#"{"access_token": "ghu_abc", "token_type": "bearer", "scope": "", "expires_in": 28800, "refresh_token": "ghr_abc", "refresh_token_expires_in": 15897600}"#,
+            ),
+            (
+                "/login/oauth/access_token",
+                r#"{"access_token": "ghu_def", "token_type": "bearer", "scope": "", "expires_in": 28800, "refresh_token": "ghr_def", "refresh_token_expires_in": 15897600}"#,
+            ),
+        ]);
+        let oauth = oauth(&login);
+        let url = oauth
+            .authorize_url(
+                &AuthorizeOptions::builder()
+                    .redirect_uri("http://localhost:3000/callback")
+                    .scopes(vec!["repo", "read:org"])
+                    .state("xyz")
+                    .build(),
+            )
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "http://localhost:8080/login/oauth/authorize?client_id=client-id&redirect_uri=http%3A%2F%2Flocalhost%3A3000%2Fcallback&scope=repo+read%3Aorg&state=xyz"
+        );
+
+        let token = oauth.exchange_code("code").await.unwrap();
+        assert_eq!(token.expires_in, Some(28800));
+        let refreshed = oauth
+            .refresh(token.refresh_token.as_deref().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(refreshed.access_token, "ghu_def");
+        assert!(!format!("{:?}", refreshed).contains("ghu_def"));
+
+        {
+            let requests = login.requests.lock().unwrap();
+            assert_eq!(
+                requests[0].1,
+                "client_id=client-id&client_secret=client-secret&code=code"
+            );
+            assert_eq!(
+                requests[1].1,
+                "client_id=client-id&client_secret=client-secret&grant_type=refresh_token&refresh_token=ghr_abc"
+            );
+        }
+        match oauth
+            .exchange_code("code")
+            .await
+            .map_err(crate::Error::into_kind)
+        {
+            Err(ErrorKind::UnexpectedResponse { code, .. }) => {
+                assert_eq!(code, StatusCode::NOT_FOUND)
+            }
+            otherwise => panic!("expected a 404, got {:?}", otherwise),
+        }
+    }
+
+    #[tokio::test]
+    async fn web_flow_requires_secret() {
+        let login = Login::new(vec![]);
+        let oauth = OAuth::custom("http://localhost:8080", "client-id", None, login.clone());
+        for result in [
+            oauth.exchange_code("code").await,
+            oauth.refresh("ghr_abc").await,
+        ] {
+            match result.map_err(crate::Error::into_kind) {
+                Err(ErrorKind::MissingClientSecret) => (),
+                otherwise => panic!("expected a missing secret, got {:?}", otherwise),
+            }
+        }
+        assert!(login.requests.lock().unwrap().is_empty());
+    }
+}
@@ -2,9 +2,10 @@
 use serde::Deserialize;
 
 use crate::membership::OrgMembership;
+use crate::pagination::Page;
 use crate::repositories::OrgRepositories;
 use crate::teams::OrgTeams;
-use crate::{Future, Github};
+use crate::{Future, Github, Stream};
 
 /// Provides access to label operations available for an individual organization
 pub struct Organization {
@@ -59,6 +60,21 @@ impl Organizations {
     pub fn list(&self) -> Future<Vec<Org>> {
         self.github.get(&self.path(""))
     }
+
+    /// provides a stream over all pages of organizations
+    pub fn iter(&self) -> Stream<Org> {
+        self.github.get_stream(&self.path(""))
+    }
+
+    /// provides a stream over each page of organizations
+    pub fn pages(&self) -> Stream<Page<Org>> {
+        self.github.get_page_stream(&self.path(""))
+    }
+
+    /// fetch a single page of organizations, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<Org>> {
+        self.github.get_page(&self.path(""), number)
+    }
 }
 
 pub struct UserOrganizations {
@@ -86,6 +102,21 @@ impl UserOrganizations {
     pub fn list(&self) -> Future<Vec<Org>> {
         self.github.get(&self.path(""))
     }
+
+    /// provides a stream over all pages of organizations
+    pub fn iter(&self) -> Stream<Org> {
+        self.github.get_stream(&self.path(""))
+    }
+
+    /// provides a stream over each page of organizations
+    pub fn pages(&self) -> Stream<Page<Org>> {
+        self.github.get_page_stream(&self.path(""))
+    }
+
+    /// fetch a single page of organizations, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<Org>> {
+        self.github.get_page(&self.path(""), number)
+    }
 }
 
 // representations
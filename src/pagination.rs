@@ -0,0 +1,350 @@
+//! Page-level access to paginated listings
+//!
+//! Every service streaming the items of a listing with `iter` can also fetch
+//! it a page at a time, with `pages` and `page` (`member_pages` and
+//! `members_page` for team members, `invitation_pages` and `invitations_page`
+//! for organization invitations). A `Page` carries its items along with the
+//! `Link` header's `next`, `prev`, `first` and `last` links, which can be used
+//! to navigate to other pages or to jump straight to any page number.
+//!
+//! Directory contents and contributor statistics aren't paginated by GitHub,
+//! so their `iter` has no page variants. Neither do search results, which
+//! wrap their items in an object with a total count, nor GraphQL connections,
+//! which paginate with cursors rather than page numbers. Listings only
+//! available through `list` return their first page.
+//!
+//! ```no_run
+//! use futures::prelude::*;
+//! use hubcaps::issues::IssueListOptions;
+//! use hubcaps::Github;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", None)?;
+//! let issues = github.repo("softprops", "hubcaps").issues();
+//! let options = IssueListOptions::builder().per_page(50).build();
+//!
+//! let first = issues.page(&options, 1).await?;
+//! println!("{} of {:?} pages", first.number(), first.total_pages());
+//! if let Some(last) = first.last().await? {
+//!     println!("{} issues on the last page", last.items.len());
+//! }
+//!
+//! // or walk every page, e.g. to checkpoint progress between pages
+//! let mut pages = issues.pages(&options);
+//! while let Some(page) = pages.try_next().await? {
+//!     println!("page {}: {} issues", page.number(), page.items.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+
+use futures::prelude::*;
+use futures::stream;
+use hyperx::header::{Link, RelationType};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+
+use crate::{Future, Github, Stream};
+
+/// A single page of a paginated listing
+pub struct Page<T> {
+    /// the items on this page
+    pub items: Vec<T>,
+    /// links to other pages of the listing
+    pub links: PageLinks,
+    url: Url,
+    github: Github,
+}
+
+/// The links to related pages of a listing, as provided by GitHub's `Link`
+/// response header. Links not applicable to a page, e.g. `prev` on the first
+/// page, are `None`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PageLinks {
+    pub next: Option<Url>,
+    pub prev: Option<Url>,
+    pub first: Option<Url>,
+    pub last: Option<Url>,
+}
+
+impl PageLinks {
+    pub(crate) fn new(link: Option<&Link>) -> Self {
+        let mut links = PageLinks::default();
+        for value in link.map(Link::values).unwrap_or_default() {
+            let url = match Url::parse(value.link()) {
+                Ok(url) => url,
+                Err(_) => continue,
+            };
+            for rel in value.rel().unwrap_or_default() {
+                let slot = match rel {
+                    RelationType::Next => &mut links.next,
+                    RelationType::Prev => &mut links.prev,
+                    RelationType::First => &mut links.first,
+                    RelationType::Last => &mut links.last,
+                    _ => continue,
+                };
+                *slot = Some(url.clone());
+            }
+        }
+        links
+    }
+
+    pub fn next_page(&self) -> Option<u32> {
+        self.next.as_ref().and_then(page_number)
+    }
+
+    pub fn prev_page(&self) -> Option<u32> {
+        self.prev.as_ref().and_then(page_number)
+    }
+
+    pub fn first_page(&self) -> Option<u32> {
+        self.first.as_ref().and_then(page_number)
+    }
+
+    pub fn last_page(&self) -> Option<u32> {
+        self.last.as_ref().and_then(page_number)
+    }
+}
+
+impl<T> Page<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    /// Fetch the page at the given url
+    pub(crate) fn fetch(github: Github, url: Url) -> Future<Page<T>> {
+        Box::pin(async move {
+            let (link, items) = github.get_pages_url::<Vec<T>>(&url).await?;
+            Ok(Page {
+                items,
+                links: PageLinks::new(link.as_ref()),
+                url,
+                github,
+            })
+        })
+    }
+
+    /// Stream every page of a listing, starting with the page at the given url
+    pub(crate) fn stream(github: Github, url: Url) -> Stream<Page<T>> {
+        Box::pin(stream::try_unfold(Some(url), move |url| {
+            let page = url.map(|url| Self::fetch(github.clone(), url));
+            async move {
+                match page {
+                    Some(page) => {
+                        let page = page.await?;
+                        let next = page.links.next.clone();
+                        Ok(Some((page, next)))
+                    }
+                    None => Ok(None),
+                }
+            }
+        }))
+    }
+
+    /// The url this page was fetched from
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// This page's number, starting at 1
+    pub fn number(&self) -> u32 {
+        page_number(&self.url)
+            .or_else(|| self.links.next_page().map(|next| next - 1))
+            .or_else(|| self.links.prev_page().map(|prev| prev + 1))
+            .unwrap_or(1)
+    }
+
+    /// The total number of pages, if known. GitHub omits the `last` link for
+    /// some listings which can't cheaply be counted
+    pub fn total_pages(&self) -> Option<u32> {
+        match (self.links.last_page(), &self.links.next) {
+            (Some(last), _) => Some(last),
+            (None, None) => Some(self.number()),
+            (None, Some(_)) => None,
+        }
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.links.next.is_some()
+    }
+
+    /// Fetch the next page, if there is one
+    pub fn next(&self) -> Future<Option<Page<T>>> {
+        self.follow(self.links.next.clone())
+    }
+
+    /// Fetch the previous page, if there is one
+    pub fn prev(&self) -> Future<Option<Page<T>>> {
+        self.follow(self.links.prev.clone())
+    }
+
+    /// Fetch the first page, unless this is the first page
+    pub fn first(&self) -> Future<Option<Page<T>>> {
+        self.follow(self.links.first.clone())
+    }
+
+    /// Fetch the last page, unless this is the last page
+    pub fn last(&self) -> Future<Option<Page<T>>> {
+        self.follow(self.links.last.clone())
+    }
+
+    /// Fetch the page with the given number of this listing
+    pub fn goto(&self, number: u32) -> Future<Page<T>> {
+        Self::fetch(self.github.clone(), with_page(&self.url, number))
+    }
+
+    /// Stream this page and every page following it
+    pub fn into_stream(self) -> Stream<Page<T>> {
+        let next = self.links.next.clone();
+        let github = self.github.clone();
+        let rest = match next {
+            Some(next) => Self::stream(github, next),
+            None => Box::pin(stream::empty()),
+        };
+        Box::pin(stream::once(future::ok(self)).chain(rest))
+    }
+
+    fn follow(&self, url: Option<Url>) -> Future<Option<Page<T>>> {
+        match url {
+            Some(url) => Box::pin(Self::fetch(self.github.clone(), url).map_ok(Some)),
+            None => Box::pin(future::ok(None)),
+        }
+    }
+}
+
+impl<T> IntoIterator for Page<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<T> fmt::Debug for Page<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Page")
+            .field("url", &self.url.as_str())
+            .field("items", &self.items)
+            .field("links", &self.links)
+            .finish()
+    }
+}
+
+/// The value of a url's `page` query parameter
+fn page_number(url: &Url) -> Option<u32> {
+    url.query_pairs()
+        .find(|(key, _)| key == "page")
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+/// A url with its `page` query parameter set to the given number
+pub(crate) fn with_page(url: &Url, number: u32) -> Url {
+    let mut url = url.clone();
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "page")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair("page", &number.to_string());
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use http::StatusCode;
+
+    #[test]
+    fn parses_links() {
+        let link: Link = concat!(
+            r#"<https://api.github.com/repositories/1/issues?per_page=2&page=3>; rel="next", "#,
+            r#"<https://api.github.com/repositories/1/issues?per_page=2&page=5>; rel="last", "#,
+            r#"<https://api.github.com/repositories/1/issues?per_page=2&page=1>; rel="first", "#,
+            r#"<https://api.github.com/repositories/1/issues?per_page=2&page=1>; rel="prev""#
+        )
+        .parse()
+        .unwrap();
+        let links = PageLinks::new(Some(&link));
+        assert_eq!(links.next_page(), Some(3));
+        assert_eq!(links.prev_page(), Some(1));
+        assert_eq!(links.first_page(), Some(1));
+        assert_eq!(links.last_page(), Some(5));
+        assert_eq!(PageLinks::new(None), PageLinks::default());
+    }
+
+    #[test]
+    fn sets_page_numbers() {
+        let url = Url::parse("https://api.github.com/user/repos?page=2&per_page=10").unwrap();
+        assert_eq!(
+            with_page(&url, 7).as_str(),
+            "https://api.github.com/user/repos?per_page=10&page=7"
+        );
+        let url = Url::parse("https://api.github.com/user/repos").unwrap();
+        assert_eq!(page_number(&with_page(&url, 1)), Some(1));
+        assert_eq!(page_number(&url), None);
+    }
+
+    #[tokio::test]
+    async fn streams_pages() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, "[1, 2]"),
+            (StatusCode::OK, "[3]"),
+            (StatusCode::OK, "[1, 2]"),
+        ])
+        .header(
+            0,
+            "link",
+            concat!(
+                r#"<https://api.github.com/repos/o/r/things?page=2>; rel="next", "#,
+                r#"<https://api.github.com/repos/o/r/things?page=2>; rel="last""#
+            ),
+        )
+        .header(
+            1,
+            "link",
+            r#"<https://api.github.com/repos/o/r/things?page=1>; rel="prev""#,
+        );
+        let github = transport.github(None);
+
+        let pages: Vec<Page<u32>> = github
+            .get_page_stream("/repos/o/r/things")
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].number(), 1);
+        assert_eq!(pages[0].total_pages(), Some(2));
+        assert!(pages[0].has_next());
+        assert_eq!(pages[1].number(), 2);
+        assert_eq!(pages[1].total_pages(), Some(2));
+        let items: Vec<u32> = pages.into_iter().flatten().collect();
+        assert_eq!(items, vec![1, 2, 3]);
+
+        let first = github
+            .get_page::<u32>("/repos/o/r/things?page=2", 1)
+            .await
+            .unwrap();
+        assert_eq!(first.items, vec![1, 2]);
+        let sent: Vec<_> = transport
+            .requests()
+            .iter()
+            .map(|request| request.uri().to_string())
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                "https://api.github.com/repos/o/r/things",
+                "https://api.github.com/repos/o/r/things?page=2",
+                "https://api.github.com/repos/o/r/things?page=1",
+            ]
+        );
+    }
+}
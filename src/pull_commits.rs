@@ -1,6 +1,7 @@
 //! Pull Commits interface
 use serde::Deserialize;
 
+use crate::pagination::Page;
 use crate::users::User;
 use crate::{Future, Github, Stream};
 
@@ -43,6 +44,23 @@ impl PullCommits {
             self.owner, self.repo, self.number
         ))
     }
+
+    /// provides a stream over each page of pull commits
+    pub fn pages(&self) -> Stream<Page<PullCommit>> {
+        self.github.get_page_stream(&format!(
+            "/repos/{}/{}/pulls/{}/commits",
+            self.owner, self.repo, self.number
+        ))
+    }
+
+    /// fetch a single page of pull commits, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<PullCommit>> {
+        let uri = format!(
+            "/repos/{}/{}/pulls/{}/commits",
+            self.owner, self.repo, self.number
+        );
+        self.github.get_page(&uri, number)
+    }
 }
 
 // representations
@@ -8,16 +8,18 @@ use url::form_urlencoded;
 use crate::comments::Comments;
 use crate::issues::{IssueAssignees, IssueLabels, Sort as IssueSort, State};
 use crate::labels::Label;
+use crate::pagination::Page;
 use crate::pull_commits::PullCommits;
 use crate::review_comments::ReviewComments;
 use crate::review_requests::ReviewRequests;
 use crate::users::User;
-use crate::{Future, Github, SortDirection, Stream};
+use crate::{Future, Github, MediaType, SortDirection, Stream};
 
 /// Sort directions for pull requests
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum Sort {
     /// Sort by time created
+    #[default]
     Created,
     /// Sort by last updated
     Updated,
@@ -39,12 +41,6 @@ impl fmt::Display for Sort {
     }
 }
 
-impl Default for Sort {
-    fn default() -> Sort {
-        Sort::Created
-    }
-}
-
 /// A structure for accessing interfacing with a specific pull request
 pub struct PullRequest {
     github: Github,
@@ -80,6 +76,16 @@ impl PullRequest {
         self.github.get(&self.path(""))
     }
 
+    /// Request a pull request as a unified diff
+    pub fn diff(&self) -> Future<String> {
+        self.github.get_text(&self.path(""), MediaType::Diff)
+    }
+
+    /// Request a pull request as a patch
+    pub fn patch(&self) -> Future<String> {
+        self.github.get_text(&self.path(""), MediaType::Patch)
+    }
+
     /// Return a reference to labels operations available for this pull request
     pub fn labels(&self) -> IssueLabels {
         IssueLabels::new(
@@ -217,6 +223,24 @@ impl PullRequests {
         }
         self.github.get_stream(&uri.join("?"))
     }
+
+    /// provides a stream over each page of pull requests
+    pub fn pages(&self, options: &PullListOptions) -> Stream<Page<Pull>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page_stream(&uri.join("?"))
+    }
+
+    /// fetch a single page of pull requests, starting at page 1
+    pub fn page(&self, options: &PullListOptions, number: u32) -> Future<Page<Pull>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page(&uri.join("?"), number)
+    }
 }
 
 // representations (todo: replace with derive_builder)
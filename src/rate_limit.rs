@@ -1,4 +1,31 @@
 //! Rate Limit interface
+//!
+//! Besides querying the rate limit api, every `Github` client keeps track of
+//! the `x-ratelimit-*` headers of the responses it receives. The last observed
+//! quota of each resource is available with `Github::rate_limits`, and clients
+//! can be configured to throttle themselves before the quota runs out.
+//!
+//! ```no_run
+//! use hubcaps::rate_limit::{Throttle, ThrottleStrategy};
+//! use hubcaps::Github;
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let mut github = Github::new("user-agent-name", None)?;
+//! github.set_throttle(
+//!     Throttle::builder()
+//!         .threshold(100)
+//!         .strategy(ThrottleStrategy::Spread)
+//!         .build(),
+//! );
+//! if let Some(core) = github.rate_limits().core() {
+//!     println!("{} requests left", core.remaining);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use crate::{Future, Github};
 use serde::Deserialize;
 
@@ -18,6 +45,113 @@ impl RateLimit {
     }
 }
 
+/// A snapshot of the last observed quota of each rate limited resource, keyed
+/// by the resource name GitHub reports, e.g. `core`, `search` or `graphql`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RateLimits(pub(crate) BTreeMap<String, RateLimitResourceStatus>);
+
+impl RateLimits {
+    pub fn core(&self) -> Option<&RateLimitResourceStatus> {
+        self.get("core")
+    }
+
+    pub fn search(&self) -> Option<&RateLimitResourceStatus> {
+        self.get("search")
+    }
+
+    pub fn graphql(&self) -> Option<&RateLimitResourceStatus> {
+        self.get("graphql")
+    }
+
+    pub fn get(&self, resource: &str) -> Option<&RateLimitResourceStatus> {
+        self.0.get(resource)
+    }
+
+    /// Iterate over all observed resources and their quotas
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &RateLimitResourceStatus)> {
+        self.0
+            .iter()
+            .map(|(resource, status)| (resource.as_str(), status))
+    }
+}
+
+/// How a throttled client slows down once a resource's remaining quota drops
+/// to its threshold
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ThrottleStrategy {
+    /// Spread the remaining requests evenly over the time left until the reset
+    #[default]
+    Spread,
+    /// Pause all requests until the quota resets
+    Pause,
+}
+
+/// Describes when and how a `Github` client throttles its own requests
+#[derive(Clone, Debug, PartialEq)]
+pub struct Throttle {
+    threshold: u32,
+    strategy: ThrottleStrategy,
+    max_wait: Duration,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle {
+            threshold: 100,
+            strategy: ThrottleStrategy::default(),
+            max_wait: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl Throttle {
+    pub fn builder() -> ThrottleBuilder {
+        ThrottleBuilder::default()
+    }
+
+    /// The delay to wait before sending a request against a resource with the
+    /// given quota, if any
+    pub fn delay(&self, status: &RateLimitResourceStatus) -> Option<Duration> {
+        if status.remaining > self.threshold {
+            return None;
+        }
+        let reset_in = status.reset_in()?;
+        let delay = match self.strategy {
+            ThrottleStrategy::Spread => reset_in / (status.remaining + 1),
+            ThrottleStrategy::Pause => reset_in,
+        };
+        Some(delay.min(self.max_wait))
+    }
+}
+
+#[derive(Default)]
+pub struct ThrottleBuilder(Throttle);
+
+impl ThrottleBuilder {
+    /// throttle once the remaining quota is at or below this many requests.
+    /// defaults to 100
+    pub fn threshold(&mut self, threshold: u32) -> &mut Self {
+        self.0.threshold = threshold;
+        self
+    }
+
+    pub fn strategy(&mut self, strategy: ThrottleStrategy) -> &mut Self {
+        self.0.strategy = strategy;
+        self
+    }
+
+    /// the longest a single request is held back. defaults to an hour, the
+    /// length of GitHub's rate limit window
+    pub fn max_wait(&mut self, max_wait: Duration) -> &mut Self {
+        self.0.max_wait = max_wait;
+        self
+    }
+
+    pub fn build(&self) -> Throttle {
+        self.0.clone()
+    }
+}
+
 // representations
 
 #[derive(Debug, Deserialize)]
@@ -32,9 +166,104 @@ pub struct RateLimitResourcesStatus {
     pub graphql: RateLimitResourceStatus,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
 pub struct RateLimitResourceStatus {
     pub limit: u32,
     pub remaining: u32,
     pub reset: u32, // ideally something like std::time::Duration
 }
+
+impl RateLimitResourceStatus {
+    /// Time left until the quota resets, or `None` if it already has
+    pub fn reset_in(&self) -> Option<Duration> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        u64::from(self.reset)
+            .checked_sub(now)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use http::StatusCode;
+
+    fn status(remaining: u32, reset_in: u64) -> RateLimitResourceStatus {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        RateLimitResourceStatus {
+            limit: 5000,
+            remaining,
+            reset: (now + reset_in) as u32,
+        }
+    }
+
+    #[test]
+    fn throttle_above_threshold() {
+        let throttle = Throttle::builder().threshold(10).build();
+        assert_eq!(throttle.delay(&status(11, 600)), None);
+    }
+
+    #[test]
+    fn throttle_spreads_remaining_quota() {
+        let throttle = Throttle::builder().threshold(10).build();
+        let delay = throttle.delay(&status(9, 600)).unwrap();
+        assert!(delay <= Duration::from_secs(60) && delay >= Duration::from_secs(59));
+        let delay = throttle.delay(&status(0, 600)).unwrap();
+        assert!(delay >= Duration::from_secs(599));
+    }
+
+    #[test]
+    fn throttle_pauses() {
+        let throttle = Throttle::builder()
+            .strategy(ThrottleStrategy::Pause)
+            .max_wait(Duration::from_secs(30))
+            .build();
+        assert_eq!(
+            throttle.delay(&status(100, 600)),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn throttle_ignores_past_resets() {
+        let throttle = Throttle::default();
+        assert_eq!(throttle.delay(&status(0, 0)), None);
+    }
+
+    #[tokio::test]
+    async fn tracks_rate_limits() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, r#"{"login": "octocat"}"#),
+            (StatusCode::OK, r#"{"total_count": 0}"#),
+        ])
+        .header_all("x-ratelimit-limit", "5000")
+        .header_all("x-ratelimit-remaining", "4999")
+        .header_all("x-ratelimit-reset", "0")
+        .header(0, "x-ratelimit-resource", "core")
+        .header(1, "x-ratelimit-resource", "search");
+        let github = transport.github(None);
+        assert_eq!(github.rate_limits(), RateLimits::default());
+
+        github.get::<serde_json::Value>("/user").await.unwrap();
+        github
+            .clone()
+            .get::<serde_json::Value>("/search/code?q=hubcaps")
+            .await
+            .unwrap();
+
+        let rate_limits = github.rate_limits();
+        let expected = RateLimitResourceStatus {
+            limit: 5000,
+            remaining: 4999,
+            reset: 0,
+        };
+        assert_eq!(rate_limits.core(), Some(&expected));
+        assert_eq!(rate_limits.search(), Some(&expected));
+        assert_eq!(rate_limits.graphql(), None);
+    }
+}
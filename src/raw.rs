@@ -0,0 +1,386 @@
+//! Raw requests
+//!
+//! An escape hatch for endpoints hubcaps doesn't wrap yet. Raw requests go
+//! through the same credentials, middleware, caching, retries and error
+//! handling as every other request.
+//!
+//! ```no_run
+//! use futures::prelude::*;
+//! use hubcaps::{Github, MediaType};
+//! use serde_json::Value;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", None)?;
+//! let views: Value = github
+//!     .raw()
+//!     .get("/repos/softprops/hubcaps/traffic/views")
+//!     .query("per", "week")
+//!     .send()
+//!     .await?;
+//!
+//! let readme = github
+//!     .raw()
+//!     .get("/repos/softprops/hubcaps/readme")
+//!     .media(MediaType::Raw)
+//!     .bytes()
+//!     .await?;
+//!
+//! let mut events = github
+//!     .raw()
+//!     .get("/repos/softprops/hubcaps/events")
+//!     .stream::<Value>();
+//! while let Some(event) = events.try_next().await? {
+//!     println!("{}", event["type"]);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use bytes::Bytes;
+use futures::prelude::*;
+use futures::{future, stream};
+use http::Method;
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::pagination::Page;
+use crate::{
+    unfold, AuthenticationConstraint, Error, ErrorKind, Future, Github, MediaType, Result, Stream,
+};
+
+/// Provides raw access to any GitHub api endpoint
+pub struct Raw {
+    github: Github,
+}
+
+impl Raw {
+    #[doc(hidden)]
+    pub fn new(github: Github) -> Self {
+        Self { github }
+    }
+
+    /// A request of any method. `path` is either relative to the client's
+    /// host, e.g. `/repos/softprops/hubcaps`, or an absolute url on its api,
+    /// uploads or GraphQL host. Requests to other hosts fail with
+    /// `ErrorKind::ForeignUrl` rather than being sent the client's credentials
+    pub fn request<P>(&self, method: Method, path: P) -> RawRequest
+    where
+        P: Into<String>,
+    {
+        RawRequest {
+            github: self.github.clone(),
+            method,
+            path: path.into(),
+            query: Vec::new(),
+            body: None,
+            media: MediaType::Json,
+            authentication: AuthenticationConstraint::Unconstrained,
+            paginated: false,
+        }
+    }
+
+    pub fn get<P>(&self, path: P) -> RawRequest
+    where
+        P: Into<String>,
+    {
+        self.request(Method::GET, path)
+    }
+
+    pub fn post<P>(&self, path: P) -> RawRequest
+    where
+        P: Into<String>,
+    {
+        self.request(Method::POST, path)
+    }
+
+    pub fn put<P>(&self, path: P) -> RawRequest
+    where
+        P: Into<String>,
+    {
+        self.request(Method::PUT, path)
+    }
+
+    pub fn patch<P>(&self, path: P) -> RawRequest
+    where
+        P: Into<String>,
+    {
+        self.request(Method::PATCH, path)
+    }
+
+    pub fn delete<P>(&self, path: P) -> RawRequest
+    where
+        P: Into<String>,
+    {
+        self.request(Method::DELETE, path)
+    }
+}
+
+/// A request to send with `send`, `bytes`, `stream` or `pages`
+pub struct RawRequest {
+    github: Github,
+    method: Method,
+    path: String,
+    query: Vec<(String, String)>,
+    body: Option<Result<Vec<u8>>>,
+    media: MediaType,
+    authentication: AuthenticationConstraint,
+    /// whether the request is for a paginated listing, which asks for the
+    /// client's default number of items per page
+    paginated: bool,
+}
+
+impl RawRequest {
+    /// Append a query parameter
+    pub fn query<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: ToString,
+    {
+        self.query.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Send the given value as the request's JSON body
+    pub fn json<B>(mut self, body: &B) -> Self
+    where
+        B: Serialize,
+    {
+        self.body = Some(serde_json::to_vec(body).map_err(Error::from));
+        self
+    }
+
+    /// The media type to accept. Defaults to `MediaType::Json`
+    pub fn media(mut self, media: MediaType) -> Self {
+        self.media = media;
+        self
+    }
+
+    /// Authenticate with a GitHub App's JWT rather than an installation token
+    pub fn jwt(mut self) -> Self {
+        self.authentication = AuthenticationConstraint::JWT;
+        self
+    }
+
+    /// Send the request, decoding the response's JSON body. Empty responses,
+    /// such as `204 No Content`, decode as `()` or `Value::Null`
+    pub fn send<T>(mut self) -> Future<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        match self.prepare() {
+            Ok((url, body)) => Box::pin(
+                self.github
+                    .request(self.method, &url, body, self.media, self.authentication)
+                    .map_ok(|(_, entity)| entity),
+            ),
+            Err(err) => Box::pin(future::err(err)),
+        }
+    }
+
+    /// Send the request, resolving to the response's body as is
+    pub fn bytes(mut self) -> Future<Bytes> {
+        match self.prepare() {
+            Ok((url, body)) => {
+                self.github
+                    .request_bytes(self.method, &url, body, self.media, self.authentication)
+            }
+            Err(err) => Box::pin(future::err(err)),
+        }
+    }
+
+    /// Stream the items of every page of a paginated listing
+    pub fn stream<T>(mut self) -> Stream<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.paginated = true;
+        match self.prepare() {
+            Ok((url, body)) => {
+                let first =
+                    self.github
+                        .request(self.method, &url, body, self.media, self.authentication);
+                unfold(self.github, first, |items: Vec<T>| items)
+            }
+            Err(err) => Box::pin(stream::once(future::err(err))),
+        }
+    }
+
+    /// Stream every page of a paginated `GET` listing
+    pub fn pages<T>(mut self) -> Stream<Page<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.paginated = true;
+        match self.url() {
+            Ok(url) => Page::stream(self.github, url),
+            Err(err) => Box::pin(stream::once(future::err(err))),
+        }
+    }
+
+    /// The request's url and body, failing if either is invalid
+    fn prepare(&mut self) -> Result<(String, Option<Vec<u8>>)> {
+        let url = self.url()?;
+        let body = self.body.take().transpose()?;
+        Ok((url.into(), body))
+    }
+
+    fn url(&self) -> Result<Url> {
+        let mut url = if self.path.starts_with("http://") || self.path.starts_with("https://") {
+            let url = Url::parse(&self.path)?;
+            if !self.github.is_own_url(&url) {
+                return Err(ErrorKind::ForeignUrl {
+                    url: self.path.clone(),
+                }
+                .into());
+            }
+            url
+        } else {
+            Url::parse(&(self.github.host.clone() + &self.path))?
+        };
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+        if self.paginated && self.method == Method::GET {
+            self.github.paginate(&mut url);
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use crate::{Credentials, Error, ErrorKind};
+    use http::header::AUTHORIZATION;
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn raw_requests() {
+        let transport = Scripted::new(vec![
+            (StatusCode::CREATED, r#"{"id": 1}"#),
+            (StatusCode::NO_CONTENT, ""),
+            (StatusCode::OK, "[1, 2]"),
+            (StatusCode::OK, "[3]"),
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                r#"{"message": "Validation Failed"}"#,
+            ),
+        ])
+        .header(
+            2,
+            "link",
+            r#"<https://api.github.com/repos/o/r/things?page=2>; rel="next""#,
+        );
+        let github = transport.github(Some(Credentials::Token("t".into())));
+
+        let created: serde_json::Value = github
+            .raw()
+            .post("/repos/o/r/things")
+            .query("draft", true)
+            .json(&serde_json::json!({"name": "thing"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(created["id"], 1);
+        github
+            .raw()
+            .delete("/repos/o/r/things/1")
+            .send::<()>()
+            .await
+            .unwrap();
+        let things: Vec<u32> = github
+            .raw()
+            .get("/repos/o/r/things")
+            .stream::<u32>()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(things, vec![1, 2, 3]);
+        match github
+            .raw()
+            .patch("https://api.github.com/repos/o/r/things/1")
+            .send::<serde_json::Value>()
+            .await
+            .map_err(Error::into_kind)
+        {
+            Err(ErrorKind::Fault { code, .. }) => {
+                assert_eq!(code, StatusCode::UNPROCESSABLE_ENTITY)
+            }
+            otherwise => panic!("expected a 422, got {:?}", otherwise),
+        }
+
+        let requests = transport.requests();
+        let sent: Vec<_> = requests
+            .iter()
+            .map(|r| (r.method().as_str(), r.uri().to_string()))
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                (
+                    "POST",
+                    "https://api.github.com/repos/o/r/things?draft=true".into()
+                ),
+                ("DELETE", "https://api.github.com/repos/o/r/things/1".into()),
+                ("GET", "https://api.github.com/repos/o/r/things".into()),
+                (
+                    "GET",
+                    "https://api.github.com/repos/o/r/things?page=2".into()
+                ),
+                ("PATCH", "https://api.github.com/repos/o/r/things/1".into()),
+            ]
+        );
+        assert_eq!(requests[0].body(), br#"{"name":"thing"}"#);
+        assert_eq!(requests[0].headers()[AUTHORIZATION], "token t");
+    }
+
+    #[tokio::test]
+    async fn credentials_stay_on_the_clients_hosts() {
+        let transport = Scripted::new(vec![(StatusCode::OK, "{}"), (StatusCode::OK, "{}")]);
+        let github = transport.github(Some(Credentials::Token("t".into())));
+
+        for url in &[
+            "https://example.com/repos/o/r",
+            "http://api.github.com/repos/o/r",
+            "https://api.github.com.example.com/repos/o/r",
+        ] {
+            match github
+                .raw()
+                .get(*url)
+                .send::<serde_json::Value>()
+                .await
+                .map_err(Error::into_kind)
+            {
+                Err(ErrorKind::ForeignUrl { url: foreign }) => assert_eq!(foreign, *url),
+                otherwise => panic!("expected a foreign url error, got {:?}", otherwise),
+            }
+        }
+        assert!(github
+            .raw()
+            .get("https://example.com/things")
+            .pages::<u32>()
+            .try_next()
+            .await
+            .is_err());
+        assert!(transport.requests().is_empty());
+
+        github
+            .raw()
+            .get("https://uploads.github.com/repos/o/r/releases/1/assets")
+            .send::<serde_json::Value>()
+            .await
+            .unwrap();
+        github
+            .raw()
+            .post("https://api.github.com/graphql")
+            .send::<serde_json::Value>()
+            .await
+            .unwrap();
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|request| request.headers()[AUTHORIZATION] == "token t"));
+    }
+}
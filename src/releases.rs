@@ -1,8 +1,9 @@
 //! Releases interface
 use serde::{Deserialize, Serialize};
 
+use crate::pagination::Page;
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Stream};
 
 /// Provides access to assets for a release.
 /// See the [github docs](https://developer.github.com/v3/repos/releases/)
@@ -174,6 +175,21 @@ impl Releases {
         self.github.get(&self.path(""))
     }
 
+    /// Provides a stream over all pages of releases.
+    pub fn iter(&self) -> Stream<Release> {
+        self.github.get_stream(&self.path(""))
+    }
+
+    /// Provides a stream over each page of releases.
+    pub fn pages(&self) -> Stream<Page<Release>> {
+        self.github.get_page_stream(&self.path(""))
+    }
+
+    /// Fetch a single page of releases, starting at page 1.
+    pub fn page(&self, number: u32) -> Future<Page<Release>> {
+        self.github.get_page(&self.path(""), number)
+    }
+
     /// Return the latest full release. Draft releases and prereleases are not returned.
     ///
     /// See the [github docs](https://developer.github.com/v3/repos/releases/#get-the-latest-release)
@@ -2,8 +2,9 @@
 //! https://developer.github.com/v3/repos/commits/#get-a-single-commit
 use serde::Deserialize;
 
+use crate::pagination::Page;
 use crate::users::User;
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, MediaType, Stream};
 
 /// A structure for interfacing with a repository commits
 pub struct RepoCommits {
@@ -43,11 +44,37 @@ impl RepoCommits {
             .get_stream(&format!("/repos/{}/{}/commits", self.owner, self.repo))
     }
 
+    /// provides a stream over each page of repo commits
+    pub fn pages(&self) -> Stream<Page<RepoCommit>> {
+        self.github
+            .get_page_stream(&format!("/repos/{}/{}/commits", self.owner, self.repo))
+    }
+
+    /// fetch a single page of repo commits, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<RepoCommit>> {
+        self.github.get_page(
+            &format!("/repos/{}/{}/commits", self.owner, self.repo),
+            number,
+        )
+    }
+
     /// get a repo commit
     pub fn get(&self, commit_ref: &str) -> Future<RepoCommit> {
         let uri = format!("/repos/{}/{}/commits/{}", self.owner, self.repo, commit_ref);
         self.github.get::<RepoCommit>(&uri)
     }
+
+    /// get a repo commit as a unified diff
+    pub fn diff(&self, commit_ref: &str) -> Future<String> {
+        let uri = format!("/repos/{}/{}/commits/{}", self.owner, self.repo, commit_ref);
+        self.github.get_text(&uri, MediaType::Diff)
+    }
+
+    /// get a repo commit as a patch
+    pub fn patch(&self, commit_ref: &str) -> Future<String> {
+        let uri = format!("/repos/{}/{}/commits/{}", self.owner, self.repo, commit_ref);
+        self.github.get_text(&uri, MediaType::Patch)
+    }
 }
 
 // representations
@@ -15,6 +15,7 @@ use crate::hooks::Hooks;
 use crate::issues::{IssueRef, Issues};
 use crate::keys::Keys;
 use crate::labels::Labels;
+use crate::pagination::Page;
 use crate::pulls::PullRequests;
 use crate::releases::Releases;
 use crate::repo_commits::RepoCommits;
@@ -172,6 +173,24 @@ impl Repositories {
         }
         self.github.get_stream(&uri.join("?"))
     }
+
+    /// provides a stream over each page of the authenticated users repositories
+    pub fn pages(&self, options: &RepoListOptions) -> Stream<Page<Repo>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page_stream(&uri.join("?"))
+    }
+
+    /// fetch a single page of the authenticated users repositories, starting at page 1
+    pub fn page(&self, options: &RepoListOptions, number: u32) -> Future<Page<Repo>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page(&uri.join("?"), number)
+    }
 }
 
 pub struct Forks {
@@ -223,6 +242,24 @@ impl Forks {
         }
         self.github.get_stream(&uri.join("?"))
     }
+
+    /// provides a stream over each page of the forks
+    pub fn pages(&self, options: &ForkListOptions) -> Stream<Page<Repo>> {
+        let mut uri = vec![self.path()];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page_stream(&uri.join("?"))
+    }
+
+    /// fetch a single page of the forks, starting at page 1
+    pub fn page(&self, options: &ForkListOptions, number: u32) -> Future<Page<Repo>> {
+        let mut uri = vec![self.path()];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page(&uri.join("?"), number)
+    }
 }
 
 /// Provides access to the authenticated user's repositories
@@ -266,6 +303,24 @@ impl OrgRepositories {
         self.github.get_stream(&uri.join("?"))
     }
 
+    /// provides a stream over each page of an orgs's repositories
+    pub fn pages(&self, options: &OrgRepoListOptions) -> Stream<Page<Repo>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page_stream(&uri.join("?"))
+    }
+
+    /// fetch a single page of an orgs's repositories, starting at page 1
+    pub fn page(&self, options: &OrgRepoListOptions, number: u32) -> Future<Page<Repo>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page(&uri.join("?"), number)
+    }
+
     /// Create a new org repository
     /// https://developer.github.com/v3/repos/#create
     pub fn create(&self, repo: &RepoOptions) -> Future<Repo> {
@@ -310,6 +365,16 @@ impl UserRepositories {
     pub fn iter(&self, options: &UserRepoListOptions) -> Stream<Repo> {
         self.github.get_stream(&self.uri(options))
     }
+
+    /// provides a stream over each page of a user's repositories
+    pub fn pages(&self, options: &UserRepoListOptions) -> Stream<Page<Repo>> {
+        self.github.get_page_stream(&self.uri(options))
+    }
+
+    /// fetch a single page of a user's repositories, starting at page 1
+    pub fn page(&self, options: &UserRepoListOptions, number: u32) -> Future<Page<Repo>> {
+        self.github.get_page(&self.uri(options), number)
+    }
 }
 
 /// Provides access to an organization's repositories
@@ -353,6 +418,24 @@ impl OrganizationRepositories {
         }
         self.github.get_stream(&uri.join("?"))
     }
+
+    /// provides a stream over each page of an organization's repositories
+    pub fn pages(&self, options: &OrganizationRepoListOptions) -> Stream<Page<Repo>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page_stream(&uri.join("?"))
+    }
+
+    /// fetch a single page of an organization's repositories, starting at page 1
+    pub fn page(&self, options: &OrganizationRepoListOptions, number: u32) -> Future<Page<Repo>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page(&uri.join("?"), number)
+    }
 }
 
 pub struct Repository {
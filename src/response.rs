@@ -0,0 +1,182 @@
+//! Response metadata
+//!
+//! Operations resolve to just their entity. To also get at the headers of
+//! the response it was decoded from, run any operation through
+//! `Github::with_response_meta`.
+//!
+//! ```no_run
+//! use hubcaps::Github;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", None)?;
+//! let response = github
+//!     .with_response_meta(|github| github.repo("softprops", "hubcaps").get())
+//!     .await?;
+//! println!("{} {:?}", response.entity.full_name, response.meta.etag());
+//! if let Some(rate_limit) = response.meta.rate_limit() {
+//!     println!("{} requests left", rate_limit.remaining);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::header::{HeaderMap, ETAG, LAST_MODIFIED, LINK};
+use http::StatusCode;
+use hyperx::header::Link;
+
+use crate::pagination::PageLinks;
+use crate::rate_limit::RateLimitResourceStatus;
+use crate::{X_GITHUB_REQUEST_ID, X_RATELIMIT_LIMIT, X_RATELIMIT_REMAINING, X_RATELIMIT_RESET};
+
+const X_POLL_INTERVAL: &str = "x-poll-interval";
+
+/// Where a client running an operation for `Github::with_response_meta`
+/// keeps the metadata of the last response it received
+pub(crate) type MetaSlot = Arc<Mutex<Option<ResponseMeta>>>;
+
+/// An operation's entity along with the metadata of its response
+#[derive(Debug)]
+pub struct Response<T> {
+    pub entity: T,
+    pub meta: ResponseMeta,
+}
+
+impl<T> Response<T> {
+    pub fn into_entity(self) -> T {
+        self.entity
+    }
+}
+
+impl<T> Deref for Response<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.entity
+    }
+}
+
+/// The status and headers of a response. For operations sending several
+/// requests, these describe the last response received. Operations which
+/// didn't send any request have no headers
+#[derive(Clone, Debug, Default)]
+pub struct ResponseMeta {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+}
+
+impl ResponseMeta {
+    pub(crate) fn new<B>(response: &http::Response<B>) -> Self {
+        ResponseMeta {
+            status: response.status(),
+            headers: response.headers().clone(),
+        }
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.header(ETAG.as_str())
+    }
+
+    /// The raw `Last-Modified` header, e.g. `Thu, 05 Jul 2012 15:31:30 GMT`
+    pub fn last_modified(&self) -> Option<&str> {
+        self.header(LAST_MODIFIED.as_str())
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.header(X_GITHUB_REQUEST_ID)
+    }
+
+    /// How long GitHub asks clients to wait before polling this resource again
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.header(X_POLL_INTERVAL)
+            .and_then(|value| value.parse().ok())
+            .map(Duration::from_secs)
+    }
+
+    /// The quota reported by the response's rate limit headers
+    pub fn rate_limit(&self) -> Option<RateLimitResourceStatus> {
+        let header = |name| self.header(name).and_then(|value| value.parse().ok());
+        Some(RateLimitResourceStatus {
+            limit: header(X_RATELIMIT_LIMIT)?,
+            remaining: header(X_RATELIMIT_REMAINING)?,
+            reset: header(X_RATELIMIT_RESET)?,
+        })
+    }
+
+    /// The pagination links of the response's `Link` header
+    pub fn links(&self) -> PageLinks {
+        let link = self
+            .header(LINK.as_str())
+            .and_then(|value| value.parse::<Link>().ok());
+        PageLinks::new(link.as_ref())
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use http::StatusCode;
+
+    #[test]
+    fn typed_headers() {
+        let response = http::Response::builder()
+            .status(StatusCode::OK)
+            .header(ETAG, "\"abc\"")
+            .header(LAST_MODIFIED, "Thu, 05 Jul 2012 15:31:30 GMT")
+            .header(X_POLL_INTERVAL, "60")
+            .header(X_RATELIMIT_LIMIT, "5000")
+            .header(X_RATELIMIT_REMAINING, "4999")
+            .header(X_RATELIMIT_RESET, "1372700873")
+            .header(
+                LINK,
+                r#"<https://api.github.com/user/repos?page=2>; rel="next""#,
+            )
+            .body(())
+            .unwrap();
+        let meta = ResponseMeta::new(&response);
+        assert_eq!(meta.etag(), Some("\"abc\""));
+        assert_eq!(meta.last_modified(), Some("Thu, 05 Jul 2012 15:31:30 GMT"));
+        assert_eq!(meta.poll_interval(), Some(Duration::from_secs(60)));
+        assert_eq!(
+            meta.rate_limit(),
+            Some(RateLimitResourceStatus {
+                limit: 5000,
+                remaining: 4999,
+                reset: 1372700873,
+            })
+        );
+        assert_eq!(meta.links().next_page(), Some(2));
+        assert_eq!(ResponseMeta::default().rate_limit(), None);
+    }
+
+    #[tokio::test]
+    async fn response_meta() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, r#"{"login": "octocat"}"#),
+            (StatusCode::OK, r#"{"login": "hubot"}"#),
+        ])
+        .header(0, "etag", "\"abc\"");
+        let github = transport.github(None);
+
+        let response = github
+            .with_response_meta(|github| github.get::<serde_json::Value>("/users/octocat"))
+            .await
+            .unwrap();
+        assert_eq!(response["login"], "octocat");
+        assert_eq!(response.meta.status, StatusCode::OK);
+        assert_eq!(response.meta.etag(), Some("\"abc\""));
+
+        let response = github
+            .with_response_meta(|github| github.get::<serde_json::Value>("/users/hubot"))
+            .await
+            .unwrap();
+        assert_eq!(response.meta.etag(), None);
+    }
+}
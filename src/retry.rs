@@ -0,0 +1,400 @@
+//! Retry policies for transient failures
+//!
+//! A `RetryPolicy` is applied transparently by the `Github` client to every
+//! request it issues, including the follow-up page requests made while
+//! streaming paginated listings.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use hubcaps::retry::RetryPolicy;
+//! use hubcaps::Github;
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let mut github = Github::new("user-agent-name", None)?;
+//! github.set_retry_policy(
+//!     RetryPolicy::builder()
+//!         .max_attempts(5)
+//!         .wait_for_rate_limit_reset(Duration::from_secs(60))
+//!         .build(),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::hash_map::RandomState;
+use std::error::Error as StdError;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use http::{Method, Response, StatusCode};
+
+use crate::{rate_limit_reset, secondary_rate_limit, Error, ErrorKind, Result};
+
+/// Describes if, when and how often failed requests are retried
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    statuses: Vec<StatusCode>,
+    connection_errors: bool,
+    rate_limit_wait: Option<Duration>,
+    non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    pub fn builder() -> RetryPolicyBuilder {
+        RetryPolicyBuilder::default()
+    }
+
+    /// A policy which never retries. This is what `Github` clients use unless
+    /// configured otherwise
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// The maximum number of attempts, including the first one
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Exponential backoff delay to wait before the given retry attempt,
+    /// where attempt `1` is the first retry
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .checked_mul(1 << exp)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        if self.jitter {
+            delay.mul_f64(random_fraction())
+        } else {
+            delay
+        }
+    }
+
+    /// Determines whether or not a request sent with the given method should be
+    /// attempted again given the outcome of its latest attempt. Returns the duration to
+    /// wait before retrying
+    pub(crate) fn retry_delay(
+        &self,
+        method: &Method,
+        attempt: u32,
+        outcome: &Result<Response<Bytes>>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !(self.non_idempotent || is_idempotent(method)) {
+            return None;
+        }
+        match outcome {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_success() || status == StatusCode::NOT_MODIFIED {
+                    return None;
+                }
+                if let Some(reset) = rate_limit_reset(response.headers())
+                    .or_else(|| secondary_rate_limit(status, response.headers(), response.body()))
+                {
+                    return self
+                        .rate_limit_wait
+                        .filter(|max_wait| reset <= *max_wait)
+                        .map(|_| reset);
+                }
+                if self.statuses.contains(&status) {
+                    Some(self.backoff(attempt))
+                } else {
+                    None
+                }
+            }
+            Err(err) if self.connection_errors && is_transient(err) => Some(self.backoff(attempt)),
+            Err(_) => None,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            statuses: vec![
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            connection_errors: true,
+            rate_limit_wait: None,
+            non_idempotent: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RetryPolicyBuilder(RetryPolicy);
+
+impl RetryPolicyBuilder {
+    /// Maximum number of attempts, including the first one. Defaults to 3
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.0.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled for each following retry. Defaults to 500ms
+    pub fn base_delay(&mut self, delay: Duration) -> &mut Self {
+        self.0.base_delay = delay;
+        self
+    }
+
+    /// Upper bound for the delay between two attempts. Defaults to 30s
+    pub fn max_delay(&mut self, delay: Duration) -> &mut Self {
+        self.0.max_delay = delay;
+        self
+    }
+
+    /// Randomize delays between zero and the computed backoff. Defaults to true
+    pub fn jitter(&mut self, jitter: bool) -> &mut Self {
+        self.0.jitter = jitter;
+        self
+    }
+
+    /// Response statuses considered transient. Defaults to 500, 502, 503 and 504
+    pub fn statuses<S>(&mut self, statuses: S) -> &mut Self
+    where
+        S: IntoIterator<Item = StatusCode>,
+    {
+        self.0.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Retry requests which failed to connect, timed out or were reset. Defaults to true
+    pub fn connection_errors(&mut self, retry: bool) -> &mut Self {
+        self.0.connection_errors = retry;
+        self
+    }
+
+    /// Wait out an exhausted or secondary rate limit, instead of failing with
+    /// `ErrorKind::RateLimit` or `ErrorKind::SecondaryRateLimit`, when it resets within the given duration
+    pub fn wait_for_rate_limit_reset(&mut self, max_wait: Duration) -> &mut Self {
+        self.0.rate_limit_wait = Some(max_wait);
+        self
+    }
+
+    /// Also retry non-idempotent `POST` and `PATCH` requests. Defaults to false
+    pub fn non_idempotent(&mut self, retry: bool) -> &mut Self {
+        self.0.non_idempotent = retry;
+        self
+    }
+
+    pub fn build(&self) -> RetryPolicy {
+        self.0.clone()
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    !matches!(*method, Method::POST | Method::PATCH)
+}
+
+/// Whether a request failed to connect, timed out or had its connection reset.
+/// Other failures, e.g. a malformed request or an undecodable body, would fail
+/// again
+fn is_transient(err: &Error) -> bool {
+    match err.kind() {
+        ErrorKind::Reqwest(err) => {
+            err.is_connect() || err.is_timeout() || io_source(err).is_some_and(is_reset)
+        }
+        ErrorKind::IO(err) => is_reset(err),
+        _ => false,
+    }
+}
+
+/// The IO error underlying an error, if there is one
+fn io_source<'a>(err: &'a (dyn StdError + 'static)) -> Option<&'a io::Error> {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<io::Error>() {
+            return Some(err);
+        }
+        source = err.source();
+    }
+    None
+}
+
+fn is_reset(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::TimedOut
+    )
+}
+
+/// A number in `[0, 1]`, random enough to spread out retries
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    hasher.finish() as f64 / u64::MAX as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use http::header::HeaderValue;
+
+    fn response(status: StatusCode) -> Result<Response<Bytes>> {
+        let mut response = Response::new(Bytes::new());
+        *response.status_mut() = status;
+        Ok(response)
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_bounded() {
+        let policy = RetryPolicy::builder()
+            .base_delay(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(5))
+            .jitter(false)
+            .build();
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(4), Duration::from_secs(5));
+        assert_eq!(policy.backoff(100), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn jitter_stays_within_backoff() {
+        let policy = RetryPolicy::builder()
+            .base_delay(Duration::from_secs(1))
+            .build();
+        for attempt in 1..5 {
+            assert!(policy.backoff(attempt) <= Duration::from_secs(1 << (attempt - 1)));
+        }
+    }
+
+    #[test]
+    fn none_never_retries() {
+        let policy = RetryPolicy::none();
+        assert_eq!(
+            policy.retry_delay(&Method::GET, 1, &response(StatusCode::BAD_GATEWAY)),
+            None
+        );
+    }
+
+    #[test]
+    fn retries_transient_statuses_of_idempotent_methods() {
+        let policy = RetryPolicy::builder().jitter(false).build();
+        assert!(policy
+            .retry_delay(&Method::GET, 1, &response(StatusCode::BAD_GATEWAY))
+            .is_some());
+        assert!(policy
+            .retry_delay(&Method::GET, 3, &response(StatusCode::BAD_GATEWAY))
+            .is_none());
+        assert!(policy
+            .retry_delay(&Method::GET, 1, &response(StatusCode::NOT_FOUND))
+            .is_none());
+        assert!(policy
+            .retry_delay(&Method::GET, 1, &response(StatusCode::OK))
+            .is_none());
+        assert!(policy
+            .retry_delay(&Method::POST, 1, &response(StatusCode::BAD_GATEWAY))
+            .is_none());
+
+        let policy = RetryPolicy::builder().non_idempotent(true).build();
+        assert!(policy
+            .retry_delay(&Method::POST, 1, &response(StatusCode::BAD_GATEWAY))
+            .is_some());
+    }
+
+    #[test]
+    fn retries_only_connection_failures() {
+        let policy = RetryPolicy::builder().jitter(false).build();
+        let failure = |kind| -> Result<Response<Bytes>> { Err(io::Error::from(kind).into()) };
+        for kind in [
+            io::ErrorKind::ConnectionRefused,
+            io::ErrorKind::ConnectionReset,
+            io::ErrorKind::TimedOut,
+        ] {
+            assert!(policy
+                .retry_delay(&Method::GET, 1, &failure(kind))
+                .is_some());
+        }
+        for kind in [
+            io::ErrorKind::NotFound,
+            io::ErrorKind::InvalidData,
+            io::ErrorKind::PermissionDenied,
+        ] {
+            assert!(policy
+                .retry_delay(&Method::GET, 1, &failure(kind))
+                .is_none());
+        }
+        let invalid: Result<Response<Bytes>> =
+            Err(serde_json::from_str::<u32>("nope").unwrap_err().into());
+        assert!(policy.retry_delay(&Method::GET, 1, &invalid).is_none());
+    }
+
+    #[test]
+    fn waits_for_rate_limit_reset_when_configured() {
+        let reset = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 30;
+        let mut limited = Response::new(Bytes::new());
+        *limited.status_mut() = StatusCode::FORBIDDEN;
+        limited
+            .headers_mut()
+            .insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        limited.headers_mut().insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_str(&reset.to_string()).unwrap(),
+        );
+        let limited = Ok(limited);
+
+        assert_eq!(
+            RetryPolicy::default().retry_delay(&Method::GET, 1, &limited),
+            None
+        );
+        assert!(RetryPolicy::builder()
+            .wait_for_rate_limit_reset(Duration::from_secs(60))
+            .build()
+            .retry_delay(&Method::GET, 1, &limited)
+            .is_some());
+        assert_eq!(
+            RetryPolicy::builder()
+                .wait_for_rate_limit_reset(Duration::from_secs(10))
+                .build()
+                .retry_delay(&Method::GET, 1, &limited),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let transport = Scripted::new(vec![
+            (StatusCode::BAD_GATEWAY, ""),
+            (StatusCode::OK, r#"{"login": "octocat"}"#),
+        ]);
+        let mut github = transport.github(None);
+        github.set_retry_policy(
+            RetryPolicy::builder()
+                .base_delay(Duration::from_millis(1))
+                .build(),
+        );
+
+        let user: serde_json::Value = github.get("/user").await.unwrap();
+        assert_eq!(user["login"], "octocat");
+        assert_eq!(transport.requests().len(), 2);
+    }
+}
@@ -2,6 +2,7 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use futures::{future, stream};
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use url::{self, form_urlencoded};
@@ -70,7 +71,10 @@ impl Search {
     where
         D: DeserializeOwned + 'static + Send,
     {
-        unfold(self.github.clone(), self.github.get_pages(url), items)
+        match self.github.listing_url(url) {
+            Ok(url) => unfold(self.github.clone(), self.github.get_pages_url(&url), items),
+            Err(err) => Box::pin(stream::once(future::err(err))),
+        }
     }
 
     fn search<D>(&self, url: &str) -> Future<SearchResult<D>>
@@ -201,6 +201,7 @@ pub struct ReposItem {
     pub score: f64,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct License {
     key: String,
@@ -2,9 +2,10 @@
 use futures::prelude::*;
 use http::StatusCode;
 
+use crate::pagination::Page;
 use crate::repositories::Repo;
 use crate::Stream;
-use crate::{Error, Future, Github};
+use crate::{ErrorKind, Future, Github};
 
 pub struct Stars {
     github: Github,
@@ -27,13 +28,17 @@ impl Stars {
                 .get::<()>(&format!("/user/starred/{}/{}", owner.into(), repo.into()))
                 .map_ok(|_| true)
                 .or_else(|err| async move {
-                    match err {
-                        Error::Fault {
+                    match err.kind() {
+                        ErrorKind::Fault {
+                            code: StatusCode::NOT_FOUND,
+                            ..
+                        }
+                        | ErrorKind::UnexpectedResponse {
                             code: StatusCode::NOT_FOUND,
                             ..
                         } => Ok(false),
-                        Error::Codec(_) => Ok(true),
-                        otherwise => Err(otherwise),
+                        ErrorKind::Codec(_) => Ok(true),
+                        _ => Err(err),
                     }
                 }),
         )
@@ -78,4 +83,22 @@ impl Stars {
         self.github
             .get_stream(&format!("/users/{}/starred", username.into()))
     }
+
+    /// provides a stream over each page of starred repos
+    pub fn pages<U>(&self, username: U) -> Stream<Page<Repo>>
+    where
+        U: Into<String>,
+    {
+        self.github
+            .get_page_stream(&format!("/users/{}/starred", username.into()))
+    }
+
+    /// fetch a single page of starred repos, starting at page 1
+    pub fn page<U>(&self, username: U, number: u32) -> Future<Page<Repo>>
+    where
+        U: Into<String>,
+    {
+        self.github
+            .get_page(&format!("/users/{}/starred", username.into()), number)
+    }
 }
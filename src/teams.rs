@@ -3,6 +3,7 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+use crate::pagination::Page;
 use crate::users::User;
 use crate::{Future, Github, Stream};
 
@@ -57,6 +58,20 @@ impl RepoTeams {
         self.github
             .get_stream(&format!("/repos/{}/{}/teams", self.owner, self.repo))
     }
+
+    /// provides a stream over each page of teams
+    pub fn pages(&self) -> Stream<Page<Team>> {
+        self.github
+            .get_page_stream(&format!("/repos/{}/{}/teams", self.owner, self.repo))
+    }
+
+    /// fetch a single page of teams, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<Team>> {
+        self.github.get_page(
+            &format!("/repos/{}/{}/teams", self.owner, self.repo),
+            number,
+        )
+    }
 }
 
 /// reference to teams associated with a github org
@@ -99,6 +114,18 @@ impl OrgTeams {
         self.github.get_stream(&format!("/orgs/{}/teams", self.org))
     }
 
+    /// provides a stream over each page of teams
+    pub fn pages(&self) -> Stream<Page<Team>> {
+        self.github
+            .get_page_stream(&format!("/orgs/{}/teams", self.org))
+    }
+
+    /// fetch a single page of teams, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<Team>> {
+        self.github
+            .get_page(&format!("/orgs/{}/teams", self.org), number)
+    }
+
     /// adds a repository permission to this team
     /// learn more [here](https://developer.github.com/v3/orgs/teams/#add-or-update-team-repository)
     pub fn add_repo_permission<N>(
@@ -158,6 +185,16 @@ impl OrgTeamActions {
         self.github.get_stream(&self.path("/members"))
     }
 
+    /// provides a stream over each page of members
+    pub fn member_pages(&self) -> Stream<Page<User>> {
+        self.github.get_page_stream(&self.path("/members"))
+    }
+
+    /// fetch a single page of members, starting at page 1
+    pub fn members_page(&self, number: u32) -> Future<Page<User>> {
+        self.github.get_page(&self.path("/members"), number)
+    }
+
     /// add a user to the team, if they are already on the team,
     /// change the role. If the user is not yet part of the
     /// organization, they are invited to join.
@@ -0,0 +1,1188 @@
+//! An in-memory stand-in for the GitHub API
+//!
+//! `FakeGithub` is an `HttpTransport` which answers requests from an in-memory,
+//! stateful model of repositories, issues, pull requests, labels, comments,
+//! statuses and releases instead of going over the network. It speaks the same
+//! JSON as api.github.com, paginates listings with `Link` headers and reports
+//! rate limits with the usual `x-ratelimit-*` headers.
+//!
+//! This module requires the `testing` cargo feature.
+//!
+//! ```no_run
+//! use hubcaps::issues::IssueOptions;
+//! use hubcaps::testing::FakeGithub;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let fake = FakeGithub::new();
+//! fake.add_repo("octocat", "hello-world");
+//!
+//! let github = fake.client();
+//! let issue = github
+//!     .repo("octocat", "hello-world")
+//!     .issues()
+//!     .create(&IssueOptions::new("found a bug", None::<String>, None::<String>, None, Vec::<String>::new()))
+//!     .await?;
+//! assert_eq!(issue.number, 1);
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use chrono::{SecondsFormat, Utc};
+use futures::future;
+use http::header::{HeaderValue, CONTENT_TYPE, ETAG, IF_NONE_MATCH, LINK};
+use http::{Method, Request, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use serde_json::{Map, Value};
+use url::Url;
+
+use crate::transport::HttpTransport;
+use crate::{Credentials, Future, Github};
+
+/// The host `FakeGithub` clients are configured with
+pub const HOST: &str = "https://api.github.com";
+const HTML_HOST: &str = "https://github.com";
+const DEFAULT_PER_PAGE: usize = 30;
+const MAX_PER_PAGE: usize = 100;
+const DEFAULT_RATE_LIMIT: u32 = 5000;
+
+/// A request received by a `FakeGithub`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedRequest {
+    pub method: Method,
+    /// path and query of the request uri
+    pub path: String,
+}
+
+/// An in-memory GitHub API. Clones share the same state
+#[derive(Clone, Debug)]
+pub struct FakeGithub {
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeGithub {
+    /// Create an empty fake. Requests are made on behalf of the `octocat` user
+    pub fn new() -> Self {
+        Self::as_user("octocat")
+    }
+
+    /// Create an empty fake, answering requests on behalf of the given user
+    pub fn as_user<L>(login: L) -> Self
+    where
+        L: Into<String>,
+    {
+        let login = login.into();
+        let mut state = State {
+            login: login.clone(),
+            next_id: 1,
+            limit: DEFAULT_RATE_LIMIT,
+            remaining: DEFAULT_RATE_LIMIT,
+            reset: now() + 3600,
+            users: BTreeMap::new(),
+            repos: BTreeMap::new(),
+            requests: Vec::new(),
+        };
+        state.user(&login);
+        FakeGithub {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// A `Github` client sending its requests to this fake
+    pub fn client(&self) -> Github {
+        Github::builder("hubcaps-testing")
+            .host(HOST)
+            .credentials(Credentials::Token("fake-token".into()))
+            .finish(Arc::new(self.clone()))
+    }
+
+    /// Add a repository owned by the given user or organization
+    pub fn add_repo<O, R>(&self, owner: O, name: R)
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        let mut state = self.state.lock().unwrap();
+        state.create_repo(&owner.into(), &Map::new(), name.into());
+    }
+
+    /// Set the core rate limit quota and how much of it remains
+    pub fn set_rate_limit(&self, limit: u32, remaining: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = limit;
+        state.remaining = remaining;
+    }
+
+    /// All requests received so far, oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Default for FakeGithub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpTransport for FakeGithub {
+    fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Bytes>> {
+        let response = self.state.lock().unwrap().handle(&request);
+        Box::pin(future::ok(response))
+    }
+}
+
+/// The outcome of routing a request
+struct Reply {
+    status: StatusCode,
+    body: Option<Value>,
+    link: Option<String>,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Self {
+        Self::status(StatusCode::OK, body)
+    }
+
+    fn created(body: Value) -> Self {
+        Self::status(StatusCode::CREATED, body)
+    }
+
+    fn no_content() -> Self {
+        Reply {
+            status: StatusCode::NO_CONTENT,
+            body: None,
+            link: None,
+        }
+    }
+
+    fn status(status: StatusCode, body: Value) -> Self {
+        Reply {
+            status,
+            body: Some(body),
+            link: None,
+        }
+    }
+
+    fn error(status: StatusCode, message: &str) -> Self {
+        Self::status(
+            status,
+            serde_json::json!({
+                "message": message,
+                "documentation_url": "https://docs.github.com/rest"
+            }),
+        )
+    }
+
+    fn not_found() -> Self {
+        Self::error(StatusCode::NOT_FOUND, "Not Found")
+    }
+
+    fn invalid(message: &str) -> Self {
+        Self::status(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            serde_json::json!({
+                "message": "Validation Failed",
+                "errors": [{ "resource": "Resource", "code": "custom", "message": message }],
+                "documentation_url": "https://docs.github.com/rest"
+            }),
+        )
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    login: String,
+    next_id: u64,
+    limit: u32,
+    remaining: u32,
+    reset: u64,
+    users: BTreeMap<String, Value>,
+    repos: BTreeMap<String, RepoState>,
+    requests: Vec<RecordedRequest>,
+}
+
+#[derive(Debug, Default)]
+struct RepoState {
+    repo: Value,
+    next_number: u64,
+    /// issues, including those backing pull requests
+    issues: Vec<Value>,
+    pulls: Vec<Value>,
+    labels: Vec<Value>,
+    comments: BTreeMap<u64, Vec<Value>>,
+    statuses: BTreeMap<String, Vec<Value>>,
+    releases: Vec<Value>,
+}
+
+impl State {
+    fn handle(&mut self, request: &Request<Vec<u8>>) -> Response<Bytes> {
+        let url = Url::parse(&request.uri().to_string()).expect("requests have absolute uris");
+        self.requests.push(RecordedRequest {
+            method: request.method().clone(),
+            path: request
+                .uri()
+                .path_and_query()
+                .map(|p| p.to_string())
+                .unwrap_or_default(),
+        });
+        let segments: Vec<String> = url
+            .path_segments()
+            .map(|segments| {
+                segments
+                    .filter(|s| !s.is_empty())
+                    .map(|s| percent_decode_str(s).decode_utf8_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let metered = segments != ["rate_limit"];
+
+        let reply = if metered && self.remaining == 0 {
+            Reply::error(
+                StatusCode::FORBIDDEN,
+                &format!("API rate limit exceeded for {}.", self.login),
+            )
+        } else {
+            let body = if request.body().is_empty() {
+                Value::Null
+            } else {
+                match serde_json::from_slice(request.body()) {
+                    Ok(body) => body,
+                    Err(_) => {
+                        return self.respond(
+                            request,
+                            Reply::invalid("Problems parsing JSON"),
+                            false,
+                        )
+                    }
+                }
+            };
+            match self.route(request.method(), &segments, &url, body) {
+                Some(reply) => reply,
+                None => Reply::not_found(),
+            }
+        };
+        self.respond(request, reply, metered)
+    }
+
+    fn respond(
+        &mut self,
+        request: &Request<Vec<u8>>,
+        reply: Reply,
+        metered: bool,
+    ) -> Response<Bytes> {
+        let body = reply
+            .body
+            .map(|body| serde_json::to_vec(&body).expect("values serialize"))
+            .unwrap_or_default();
+        let etag = format!("\"{:016x}\"", {
+            let mut hasher = DefaultHasher::new();
+            body.hash(&mut hasher);
+            hasher.finish()
+        });
+        let not_modified = reply.status == StatusCode::OK
+            && request.method() == Method::GET
+            && request
+                .headers()
+                .get(IF_NONE_MATCH)
+                .is_some_and(|tag| tag.as_bytes() == etag.as_bytes());
+        // like GitHub, conditional requests answered with a 304 are free
+        if metered && !not_modified && self.remaining > 0 {
+            self.remaining -= 1;
+        }
+        let request_id = format!("FAKE:{:08X}", self.requests.len());
+        let mut response = Response::builder()
+            .status(if not_modified {
+                StatusCode::NOT_MODIFIED
+            } else {
+                reply.status
+            })
+            .header("x-github-request-id", request_id)
+            .header("x-ratelimit-limit", self.limit)
+            .header("x-ratelimit-remaining", self.remaining)
+            .header("x-ratelimit-reset", self.reset)
+            .header("x-ratelimit-resource", "core");
+        if !body.is_empty() {
+            response = response
+                .header(CONTENT_TYPE, "application/json; charset=utf-8")
+                .header(
+                    ETAG,
+                    HeaderValue::from_str(&etag).expect("hex is a valid header"),
+                );
+        }
+        if let Some(link) = reply.link {
+            response = response.header(LINK, link);
+        }
+        let body = if not_modified {
+            Bytes::new()
+        } else {
+            Bytes::from(body)
+        };
+        response.body(body).expect("valid response")
+    }
+
+    fn route(
+        &mut self,
+        method: &Method,
+        segments: &[&str],
+        url: &Url,
+        body: Value,
+    ) -> Option<Reply> {
+        let reply = match (method, segments) {
+            (&Method::GET, ["rate_limit"]) => {
+                let core = serde_json::json!({
+                    "limit": self.limit,
+                    "remaining": self.remaining,
+                    "reset": self.reset
+                });
+                Reply::ok(serde_json::json!({
+                    "resources": { "core": core, "search": core, "graphql": core },
+                    "rate": core
+                }))
+            }
+            (&Method::GET, ["user"]) => {
+                let login = self.login.clone();
+                Reply::ok(self.user(&login))
+            }
+            (&Method::GET, ["users", login]) => Reply::ok(self.user(login)),
+            (&Method::GET, ["user", "repos"]) => {
+                let login = self.login.clone();
+                self.list_repos(&login, url)
+            }
+            (&Method::POST, ["user", "repos"]) => {
+                let login = self.login.clone();
+                self.create_repo_from(&login, body)
+            }
+            (&Method::GET, ["users", owner, "repos"])
+            | (&Method::GET, ["orgs", owner, "repos"]) => self.list_repos(owner, url),
+            (&Method::POST, ["orgs", owner, "repos"]) => self.create_repo_from(owner, body),
+            (_, ["repos", owner, repo, rest @ ..]) => {
+                let key = format!("{}/{}", owner, repo);
+                if !self.repos.contains_key(&key) {
+                    return None;
+                }
+                self.repo_route(method, &key, rest, url, body)?
+            }
+            _ => return None,
+        };
+        Some(reply)
+    }
+
+    fn repo_route(
+        &mut self,
+        method: &Method,
+        key: &str,
+        segments: &[&str],
+        url: &Url,
+        body: Value,
+    ) -> Option<Reply> {
+        let user = {
+            let login = self.login.clone();
+            self.user(&login)
+        };
+        let id = self.id();
+        let repo = self.repos.get_mut(key)?;
+        let reply = match (method, segments) {
+            (&Method::GET, []) => Reply::ok(repo.repo.clone()),
+            (&Method::PATCH, []) | (&Method::POST, []) => {
+                merge(
+                    &mut repo.repo,
+                    &body,
+                    &[
+                        "name",
+                        "description",
+                        "homepage",
+                        "private",
+                        "default_branch",
+                        "has_issues",
+                        "has_wiki",
+                    ],
+                );
+                Reply::ok(repo.repo.clone())
+            }
+            (&Method::DELETE, []) => {
+                self.repos.remove(key);
+                Reply::no_content()
+            }
+
+            // labels
+            (&Method::GET, ["labels"]) => paginate(repo.labels.clone(), url),
+            (&Method::POST, ["labels"]) => {
+                let name = str_field(&body, "name")?;
+                if repo.label(name).is_some() {
+                    Reply::invalid("already_exists")
+                } else {
+                    let label = label_json(
+                        &repo.repo,
+                        name,
+                        str_field(&body, "color").unwrap_or("ededed"),
+                        body.get("description"),
+                    );
+                    repo.labels.push(label.clone());
+                    Reply::created(label)
+                }
+            }
+            (&Method::GET, ["labels", name]) => Reply::ok(repo.label(name)?.clone()),
+            (&Method::PATCH, ["labels", name]) => {
+                let label = repo.labels.iter_mut().find(|l| l["name"] == *name)?;
+                merge(label, &body, &["name", "color", "description"]);
+                let url = format!(
+                    "{}/labels/{}",
+                    repo.repo["url"].as_str().unwrap_or_default(),
+                    label["name"].as_str().unwrap_or_default()
+                );
+                label["url"] = Value::from(url);
+                Reply::ok(label.clone())
+            }
+            (&Method::DELETE, ["labels", name]) => {
+                repo.label(name)?;
+                repo.labels.retain(|l| l["name"] != *name);
+                Reply::no_content()
+            }
+
+            // issues
+            (&Method::GET, ["issues"]) => {
+                let issues = filter_state(repo.issues.iter().rev(), url);
+                paginate(issues, url)
+            }
+            (&Method::POST, ["issues"]) => {
+                let title = str_field(&body, "title")?;
+                let number = repo.number();
+                let labels = repo.resolve_labels(body.get("labels"));
+                let issue = issue_json(&repo.repo, id, number, title, &body, &user, labels);
+                repo.issues.push(issue.clone());
+                Reply::created(issue)
+            }
+            (&Method::GET, ["issues", number]) => Reply::ok(repo.issue(number)?.clone()),
+            (&Method::PATCH, ["issues", number]) => {
+                let labels = body
+                    .get("labels")
+                    .map(|names| repo.resolve_labels(Some(names)));
+                let issue = repo.issue_mut(number)?;
+                merge(issue, &body, &["title", "body", "state"]);
+                if let Some(labels) = labels {
+                    issue["labels"] = Value::from(labels);
+                }
+                touch(issue);
+                let issue = issue.clone();
+                repo.sync_pull(&issue);
+                Reply::ok(issue)
+            }
+            (&Method::POST, ["issues", number, "assignees"]) => {
+                let assignees: Vec<Value> = body["assignees"]
+                    .as_array()
+                    .map(|logins| {
+                        logins
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .map(|login| user_json(login, 0))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let issue = repo.issue_mut(number)?;
+                if let Some(existing) = issue["assignees"].as_array_mut() {
+                    existing.extend(assignees);
+                    issue["assignee"] = existing.first().cloned().unwrap_or(Value::Null);
+                }
+                Reply::created(issue.clone())
+            }
+            (&Method::GET, ["issues", number, "labels"]) => {
+                Reply::ok(repo.issue(number)?["labels"].clone())
+            }
+            (&Method::POST, ["issues", number, "labels"])
+            | (&Method::PUT, ["issues", number, "labels"]) => {
+                let labels = repo.resolve_labels(Some(&body));
+                let issue = repo.issue_mut(number)?;
+                if method == Method::PUT {
+                    issue["labels"] = Value::from(labels);
+                } else if let Some(existing) = issue["labels"].as_array_mut() {
+                    for label in labels {
+                        if !existing.contains(&label) {
+                            existing.push(label);
+                        }
+                    }
+                }
+                Reply::ok(issue["labels"].clone())
+            }
+            (&Method::DELETE, ["issues", number, "labels"]) => {
+                repo.issue_mut(number)?["labels"] = serde_json::json!([]);
+                Reply::no_content()
+            }
+            (&Method::DELETE, ["issues", number, "labels", name]) => {
+                let issue = repo.issue_mut(number)?;
+                let labels = issue["labels"].as_array_mut()?;
+                let before = labels.len();
+                labels.retain(|l| l["name"] != *name);
+                if labels.len() == before {
+                    Reply::error(StatusCode::NOT_FOUND, "Label does not exist")
+                } else {
+                    Reply::ok(issue["labels"].clone())
+                }
+            }
+            (&Method::GET, ["issues", number, "comments"]) => {
+                let number = repo.issue(number)?["number"].as_u64()?;
+                paginate(repo.comments.get(&number).cloned().unwrap_or_default(), url)
+            }
+            (&Method::POST, ["issues", number, "comments"]) => {
+                let text = str_field(&body, "body")?;
+                let issue = repo.issue_mut(number)?;
+                let number = issue["number"].as_u64()?;
+                issue["comments"] = Value::from(issue["comments"].as_u64().unwrap_or_default() + 1);
+                let comment = serde_json::json!({
+                    "id": id,
+                    "url": format!("{}/issues/comments/{}", repo.repo["url"].as_str().unwrap_or_default(), id),
+                    "html_url": format!("{}/issues/{}#issuecomment-{}", repo.repo["html_url"].as_str().unwrap_or_default(), number, id),
+                    "body": text,
+                    "user": user,
+                    "created_at": timestamp(),
+                    "updated_at": timestamp()
+                });
+                repo.comments
+                    .entry(number)
+                    .or_default()
+                    .push(comment.clone());
+                Reply::created(comment)
+            }
+
+            // pulls
+            (&Method::GET, ["pulls"]) => {
+                let pulls = filter_state(repo.pulls.iter().rev(), url);
+                paginate(pulls, url)
+            }
+            (&Method::POST, ["pulls"]) => {
+                let title = str_field(&body, "title")?;
+                let head = str_field(&body, "head")?;
+                let base = str_field(&body, "base")?;
+                if repo
+                    .pulls
+                    .iter()
+                    .any(|p| p["head"]["ref"] == head && p["state"] == "open")
+                {
+                    return Some(Reply::invalid(&format!(
+                        "A pull request already exists for {}.",
+                        head
+                    )));
+                }
+                let number = repo.number();
+                let mut issue = issue_json(&repo.repo, id, number, title, &body, &user, Vec::new());
+                issue["pull_request"] = serde_json::json!({
+                    "url": format!("{}/pulls/{}", repo.repo["url"].as_str().unwrap_or_default(), number),
+                    "html_url": format!("{}/pull/{}", repo.repo["html_url"].as_str().unwrap_or_default(), number),
+                    "diff_url": format!("{}/pull/{}.diff", repo.repo["html_url"].as_str().unwrap_or_default(), number),
+                    "patch_url": format!("{}/pull/{}.patch", repo.repo["html_url"].as_str().unwrap_or_default(), number)
+                });
+                let pull = pull_json(&repo.repo, &issue, head, base, &user);
+                repo.issues.push(issue);
+                repo.pulls.push(pull.clone());
+                Reply::created(pull)
+            }
+            (&Method::GET, ["pulls", number]) => Reply::ok(repo.pull(number)?.clone()),
+            (&Method::PATCH, ["pulls", number]) => {
+                let number: u64 = number.parse().ok()?;
+                let pull = repo.pulls.iter_mut().find(|p| p["number"] == number)?;
+                merge(pull, &body, &["title", "body", "state"]);
+                touch(pull);
+                let pull = pull.clone();
+                if let Some(issue) = repo.issues.iter_mut().find(|i| i["number"] == number) {
+                    merge(issue, &body, &["title", "body", "state"]);
+                    touch(issue);
+                }
+                Reply::ok(pull)
+            }
+            (&Method::GET, ["pulls", number, "files"]) => {
+                repo.pull(number)?;
+                Reply::ok(serde_json::json!([]))
+            }
+
+            // statuses
+            (&Method::POST, ["statuses", sha]) => {
+                let state = str_field(&body, "state")?;
+                if !["pending", "success", "error", "failure"].contains(&state) {
+                    return Some(Reply::invalid("state is not included in the list"));
+                }
+                let status = serde_json::json!({
+                    "id": id,
+                    "url": format!("{}/statuses/{}", repo.repo["url"].as_str().unwrap_or_default(), sha),
+                    "state": state,
+                    "description": body.get("description").cloned().unwrap_or_else(|| Value::from("")),
+                    "target_url": body.get("target_url").cloned().unwrap_or(Value::Null),
+                    "context": body.get("context").cloned().unwrap_or_else(|| Value::from("default")),
+                    "created_at": timestamp(),
+                    "updated_at": timestamp(),
+                    "creator": user
+                });
+                repo.statuses
+                    .entry(sha.to_string())
+                    .or_default()
+                    .insert(0, status.clone());
+                Reply::created(status)
+            }
+            (&Method::GET, ["commits", sha, "statuses"]) | (&Method::GET, ["statuses", sha]) => {
+                paginate(repo.statuses.get(*sha).cloned().unwrap_or_default(), url)
+            }
+            (&Method::GET, ["commits", sha, "status"]) => {
+                let mut latest: Vec<Value> = Vec::new();
+                for status in repo.statuses.get(*sha).cloned().unwrap_or_default() {
+                    if !latest.iter().any(|s| s["context"] == status["context"]) {
+                        latest.push(status);
+                    }
+                }
+                let state = if latest
+                    .iter()
+                    .any(|s| s["state"] == "error" || s["state"] == "failure")
+                {
+                    "failure"
+                } else if latest.is_empty() || latest.iter().any(|s| s["state"] == "pending") {
+                    "pending"
+                } else {
+                    "success"
+                };
+                Reply::ok(serde_json::json!({
+                    "state": state,
+                    "sha": sha,
+                    "total_count": latest.len(),
+                    "statuses": latest,
+                    "repository": repo.repo
+                }))
+            }
+
+            // releases
+            (&Method::GET, ["releases"]) => {
+                paginate(repo.releases.iter().rev().cloned().collect(), url)
+            }
+            (&Method::POST, ["releases"]) => {
+                let tag = str_field(&body, "tag_name")?;
+                if repo.releases.iter().any(|r| r["tag_name"] == tag) {
+                    return Some(Reply::invalid("Published releases must have a valid tag"));
+                }
+                let release = release_json(&repo.repo, id, tag, &body, &user);
+                repo.releases.push(release.clone());
+                Reply::created(release)
+            }
+            (&Method::GET, ["releases", "latest"]) => Reply::ok(
+                repo.releases
+                    .iter()
+                    .rev()
+                    .find(|r| r["draft"] == false && r["prerelease"] == false)?
+                    .clone(),
+            ),
+            (&Method::GET, ["releases", "tags", tag]) => Reply::ok(
+                repo.releases
+                    .iter()
+                    .find(|r| r["tag_name"] == *tag)?
+                    .clone(),
+            ),
+            (&Method::GET, ["releases", release_id]) => {
+                Reply::ok(repo.release_mut(release_id)?.clone())
+            }
+            (&Method::PATCH, ["releases", release_id]) => {
+                let release = repo.release_mut(release_id)?;
+                merge(
+                    release,
+                    &body,
+                    &[
+                        "tag_name",
+                        "target_commitish",
+                        "name",
+                        "body",
+                        "draft",
+                        "prerelease",
+                    ],
+                );
+                Reply::ok(release.clone())
+            }
+            (&Method::DELETE, ["releases", release_id]) => {
+                let release_id = repo.release_mut(release_id)?["id"].clone();
+                repo.releases.retain(|r| r["id"] != release_id);
+                Reply::no_content()
+            }
+            (&Method::GET, ["releases", release_id, "assets"]) => {
+                Reply::ok(repo.release_mut(release_id)?["assets"].clone())
+            }
+            _ => return None,
+        };
+        Some(reply)
+    }
+
+    fn id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn user(&mut self, login: &str) -> Value {
+        if let Some(user) = self.users.get(login) {
+            return user.clone();
+        }
+        let user = user_json(login, self.id());
+        self.users.insert(login.to_string(), user.clone());
+        user
+    }
+
+    fn list_repos(&mut self, owner: &str, url: &Url) -> Reply {
+        let repos = self
+            .repos
+            .values()
+            .filter(|repo| repo.repo["owner"]["login"] == owner)
+            .map(|repo| repo.repo.clone())
+            .collect();
+        paginate(repos, url)
+    }
+
+    fn create_repo_from(&mut self, owner: &str, body: Value) -> Reply {
+        let name = match str_field(&body, "name") {
+            Some(name) => name.to_string(),
+            None => return Reply::invalid("name is missing"),
+        };
+        if self.repos.contains_key(&format!("{}/{}", owner, name)) {
+            return Reply::invalid("name already exists on this account");
+        }
+        let options = body.as_object().cloned().unwrap_or_default();
+        Reply::created(self.create_repo(owner, &options, name))
+    }
+
+    fn create_repo(&mut self, owner: &str, options: &Map<String, Value>, name: String) -> Value {
+        let id = self.id();
+        let owner = self.user(owner);
+        let repo = repo_json(id, &owner, &name, options);
+        let key = format!("{}/{}", owner["login"].as_str().unwrap_or_default(), name);
+        self.repos.insert(
+            key,
+            RepoState {
+                repo: repo.clone(),
+                next_number: 1,
+                ..RepoState::default()
+            },
+        );
+        repo
+    }
+}
+
+impl RepoState {
+    fn number(&mut self) -> u64 {
+        let number = self.next_number;
+        self.next_number += 1;
+        self.repo["open_issues_count"] = Value::from(self.issues.len() + 1);
+        number
+    }
+
+    fn label(&self, name: &str) -> Option<&Value> {
+        self.labels.iter().find(|l| l["name"] == name)
+    }
+
+    /// look up labels by name, creating missing ones like GitHub does
+    fn resolve_labels(&mut self, names: Option<&Value>) -> Vec<Value> {
+        let names: Vec<String> = names
+            .and_then(Value::as_array)
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| name.as_str().or_else(|| name["name"].as_str()))
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        names
+            .iter()
+            .map(|name| match self.label(name) {
+                Some(label) => label.clone(),
+                None => {
+                    let label = label_json(&self.repo, name, "ededed", None);
+                    self.labels.push(label.clone());
+                    label
+                }
+            })
+            .collect()
+    }
+
+    fn issue(&self, number: &str) -> Option<&Value> {
+        let number: u64 = number.parse().ok()?;
+        self.issues.iter().find(|i| i["number"] == number)
+    }
+
+    fn issue_mut(&mut self, number: &str) -> Option<&mut Value> {
+        let number: u64 = number.parse().ok()?;
+        self.issues.iter_mut().find(|i| i["number"] == number)
+    }
+
+    fn pull(&self, number: &str) -> Option<&Value> {
+        let number: u64 = number.parse().ok()?;
+        self.pulls.iter().find(|p| p["number"] == number)
+    }
+
+    /// keep a pull request in sync with edits made through its issue
+    fn sync_pull(&mut self, issue: &Value) {
+        if let Some(pull) = self
+            .pulls
+            .iter_mut()
+            .find(|p| p["number"] == issue["number"])
+        {
+            for field in &["title", "body", "state", "labels", "updated_at"] {
+                pull[*field] = issue[*field].clone();
+            }
+        }
+    }
+
+    fn release_mut(&mut self, id: &str) -> Option<&mut Value> {
+        let id: u64 = id.parse().ok()?;
+        self.releases.iter_mut().find(|r| r["id"] == id)
+    }
+}
+
+/// Reply with the requested page of items, linking to the others
+fn paginate(items: Vec<Value>, url: &Url) -> Reply {
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+    };
+    let per_page = param("per_page")
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+    let page = param("page").unwrap_or(1).max(1);
+    let last = items.len().div_ceil(per_page).max(1);
+
+    let page_url = |page: usize| {
+        let mut url = url.clone();
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| key != "page" && key != "per_page")
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair("per_page", &per_page.to_string())
+            .append_pair("page", &page.to_string());
+        url
+    };
+    let mut links = Vec::new();
+    if page < last {
+        links.push(format!("<{}>; rel=\"next\"", page_url(page + 1)));
+        links.push(format!("<{}>; rel=\"last\"", page_url(last)));
+    }
+    if page > 1 {
+        links.push(format!("<{}>; rel=\"first\"", page_url(1)));
+        links.push(format!("<{}>; rel=\"prev\"", page_url(page - 1)));
+    }
+
+    let items: Vec<Value> = items
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .collect();
+    Reply {
+        status: StatusCode::OK,
+        body: Some(Value::from(items)),
+        link: if links.is_empty() {
+            None
+        } else {
+            Some(links.join(", "))
+        },
+    }
+}
+
+/// keep the items matching the `state` query parameter, `open` by default
+fn filter_state<'a, I>(items: I, url: &Url) -> Vec<Value>
+where
+    I: Iterator<Item = &'a Value>,
+{
+    let state = url
+        .query_pairs()
+        .find(|(key, _)| key == "state")
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_else(|| "open".into());
+    items
+        .filter(|item| state == "all" || item["state"] == state.as_str())
+        .cloned()
+        .collect()
+}
+
+fn str_field<'a>(body: &'a Value, field: &str) -> Option<&'a str> {
+    body.get(field).and_then(Value::as_str)
+}
+
+/// copy the given fields of `patch`, when present, onto `target`
+fn merge(target: &mut Value, patch: &Value, fields: &[&str]) {
+    for field in fields {
+        if let Some(value) = patch.get(*field) {
+            if !value.is_null() {
+                target[*field] = value.clone();
+            }
+        }
+    }
+    if target["state"] == "closed"
+        && target["closed_at"].is_null()
+        && target.get("closed_at").is_some()
+    {
+        target["closed_at"] = Value::from(timestamp());
+    } else if target["state"] == "open" {
+        if let Some(closed_at) = target.get_mut("closed_at") {
+            *closed_at = Value::Null;
+        }
+    }
+}
+
+fn touch(target: &mut Value) {
+    target["updated_at"] = Value::from(timestamp());
+}
+
+fn user_json(login: &str, id: u64) -> Value {
+    let url = format!("{}/users/{}", HOST, login);
+    serde_json::json!({
+        "login": login,
+        "id": id,
+        "avatar_url": format!("https://avatars.githubusercontent.com/u/{}", id),
+        "gravatar_id": "",
+        "url": url,
+        "html_url": format!("{}/{}", HTML_HOST, login),
+        "followers_url": format!("{}/followers", url),
+        "following_url": format!("{}/following{{/other_user}}", url),
+        "gists_url": format!("{}/gists{{/gist_id}}", url),
+        "starred_url": format!("{}/starred{{/owner}}{{/repo}}", url),
+        "subscriptions_url": format!("{}/subscriptions", url),
+        "organizations_url": format!("{}/orgs", url),
+        "repos_url": format!("{}/repos", url),
+        "events_url": format!("{}/events{{/privacy}}", url),
+        "received_events_url": format!("{}/received_events", url),
+        "type": "User",
+        "site_admin": false
+    })
+}
+
+fn repo_json(id: u64, owner: &Value, name: &str, options: &Map<String, Value>) -> Value {
+    let full_name = format!("{}/{}", owner["login"].as_str().unwrap_or_default(), name);
+    let url = format!("{}/repos/{}", HOST, full_name);
+    let option = |key: &str, default: Value| options.get(key).cloned().unwrap_or(default);
+    let mut repo = serde_json::json!({
+        "id": id,
+        "owner": owner,
+        "name": name,
+        "full_name": full_name,
+        "description": option("description", Value::Null),
+        "private": option("private", Value::from(false)),
+        "fork": false,
+        "url": url,
+        "html_url": format!("{}/{}", HTML_HOST, full_name),
+        "clone_url": format!("{}/{}.git", HTML_HOST, full_name),
+        "git_url": format!("git://github.com/{}.git", full_name),
+        "ssh_url": format!("git@github.com:{}.git", full_name),
+        "svn_url": format!("{}/{}", HTML_HOST, full_name),
+        "mirror_url": null,
+        "homepage": option("homepage", Value::Null),
+        "language": null,
+        "forks_count": 0,
+        "stargazers_count": 0,
+        "watchers_count": 0,
+        "size": 0,
+        "default_branch": "master",
+        "open_issues_count": 0,
+        "has_issues": option("has_issues", Value::from(true)),
+        "has_wiki": option("has_wiki", Value::from(true)),
+        "has_pages": false,
+        "has_downloads": option("has_downloads", Value::from(true)),
+        "archived": false,
+        "pushed_at": timestamp(),
+        "created_at": timestamp(),
+        "updated_at": timestamp()
+    });
+    for (field, suffix) in &[
+        ("archive_url", "/{archive_format}{/ref}"),
+        ("assignees_url", "/assignees{/user}"),
+        ("blobs_url", "/git/blobs{/sha}"),
+        ("branches_url", "/branches{/branch}"),
+        ("collaborators_url", "/collaborators{/collaborator}"),
+        ("comments_url", "/comments{/number}"),
+        ("commits_url", "/commits{/sha}"),
+        ("compare_url", "/compare/{base}...{head}"),
+        ("contents_url", "/contents/{+path}"),
+        ("contributors_url", "/contributors"),
+        ("deployments_url", "/deployments"),
+        ("downloads_url", "/downloads"),
+        ("events_url", "/events"),
+        ("forks_url", "/forks"),
+        ("git_commits_url", "/git/commits{/sha}"),
+        ("git_refs_url", "/git/refs{/sha}"),
+        ("git_tags_url", "/git/tags{/sha}"),
+        ("hooks_url", "/hooks"),
+        ("issue_comment_url", "/issues/comments{/number}"),
+        ("issue_events_url", "/issues/events{/number}"),
+        ("issues_url", "/issues{/number}"),
+        ("keys_url", "/keys{/key_id}"),
+        ("labels_url", "/labels{/name}"),
+        ("languages_url", "/languages"),
+        ("merges_url", "/merges"),
+        ("milestones_url", "/milestones{/number}"),
+        (
+            "notifications_url",
+            "/notifications{?since,all,participating}",
+        ),
+        ("pulls_url", "/pulls{/number}"),
+        ("releases_url", "/releases{/id}"),
+        ("stargazers_url", "/stargazers"),
+        ("statuses_url", "/statuses/{sha}"),
+        ("subscribers_url", "/subscribers"),
+        ("subscription_url", "/subscription"),
+        ("tags_url", "/tags"),
+        ("teams_url", "/teams"),
+        ("trees_url", "/git/trees{/sha}"),
+    ] {
+        repo[*field] = Value::from(format!("{}{}", url, suffix));
+    }
+    repo
+}
+
+fn label_json(repo: &Value, name: &str, color: &str, description: Option<&Value>) -> Value {
+    serde_json::json!({
+        "url": format!("{}/labels/{}", repo["url"].as_str().unwrap_or_default(), name),
+        "name": name,
+        "color": color,
+        "description": description.cloned().unwrap_or(Value::Null)
+    })
+}
+
+fn issue_json(
+    repo: &Value,
+    id: u64,
+    number: u64,
+    title: &str,
+    options: &Value,
+    user: &Value,
+    labels: Vec<Value>,
+) -> Value {
+    let url = format!(
+        "{}/issues/{}",
+        repo["url"].as_str().unwrap_or_default(),
+        number
+    );
+    let assignees: Vec<Value> = options
+        .get("assignee")
+        .and_then(Value::as_str)
+        .map(|login| user_json(login, 0))
+        .into_iter()
+        .collect();
+    serde_json::json!({
+        "id": id,
+        "url": url,
+        "labels_url": format!("{}/labels{{/name}}", url),
+        "comments_url": format!("{}/comments", url),
+        "events_url": format!("{}/events", url),
+        "html_url": format!("{}/issues/{}", repo["html_url"].as_str().unwrap_or_default(), number),
+        "number": number,
+        "state": "open",
+        "title": title,
+        "body": options.get("body").cloned().unwrap_or(Value::Null),
+        "user": user,
+        "labels": labels,
+        "assignee": assignees.first().cloned().unwrap_or(Value::Null),
+        "assignees": assignees,
+        "locked": false,
+        "comments": 0,
+        "closed_at": null,
+        "created_at": timestamp(),
+        "updated_at": timestamp()
+    })
+}
+
+fn pull_json(repo: &Value, issue: &Value, head: &str, base: &str, user: &Value) -> Value {
+    let number = issue["number"].as_u64().unwrap_or_default();
+    let url = format!(
+        "{}/pulls/{}",
+        repo["url"].as_str().unwrap_or_default(),
+        number
+    );
+    let html_url = format!(
+        "{}/pull/{}",
+        repo["html_url"].as_str().unwrap_or_default(),
+        number
+    );
+    let owner = repo["owner"]["login"].as_str().unwrap_or_default();
+    let commit = |branch: &str| {
+        let mut hasher = DefaultHasher::new();
+        (number, branch).hash(&mut hasher);
+        serde_json::json!({
+            "label": format!("{}:{}", owner, branch),
+            "ref": branch,
+            "sha": format!("{:016x}{:016x}{:08x}", hasher.finish(), number, number),
+            "user": repo["owner"]
+        })
+    };
+    serde_json::json!({
+        "id": issue["id"],
+        "url": url,
+        "html_url": html_url,
+        "diff_url": format!("{}.diff", html_url),
+        "patch_url": format!("{}.patch", html_url),
+        "issue_url": issue["url"],
+        "commits_url": format!("{}/commits", url),
+        "review_comments_url": format!("{}/comments", url),
+        "review_comment_url": format!("{}/pulls/comments{{/number}}", repo["url"].as_str().unwrap_or_default()),
+        "comments_url": issue["comments_url"],
+        "statuses_url": format!("{}/statuses/{{sha}}", repo["url"].as_str().unwrap_or_default()),
+        "number": number,
+        "state": "open",
+        "title": issue["title"],
+        "body": issue["body"],
+        "created_at": issue["created_at"],
+        "updated_at": issue["updated_at"],
+        "closed_at": null,
+        "merged_at": null,
+        "head": commit(head),
+        "base": commit(base),
+        "user": user,
+        "assignee": null,
+        "assignees": [],
+        "merge_commit_sha": null,
+        "merged": false,
+        "mergeable": null,
+        "merged_by": null,
+        "comments": 0,
+        "commits": 1,
+        "additions": 0,
+        "deletions": 0,
+        "changed_files": 0,
+        "labels": []
+    })
+}
+
+fn release_json(repo: &Value, id: u64, tag: &str, options: &Value, user: &Value) -> Value {
+    let repo_url = repo["url"].as_str().unwrap_or_default();
+    let url = format!("{}/releases/{}", repo_url, id);
+    serde_json::json!({
+        "url": url,
+        "html_url": format!("{}/releases/tag/{}", repo["html_url"].as_str().unwrap_or_default(), tag),
+        "assets_url": format!("{}/assets", url),
+        "upload_url": format!("https://uploads.github.com/repos/{}/releases/{}/assets{{?name,label}}", repo["full_name"].as_str().unwrap_or_default(), id),
+        "tarball_url": format!("{}/tarball/{}", repo_url, tag),
+        "zipball_url": format!("{}/zipball/{}", repo_url, tag),
+        "id": id,
+        "tag_name": tag,
+        "target_commitish": options.get("target_commitish").cloned().unwrap_or_else(|| Value::from("master")),
+        "name": options.get("name").cloned().unwrap_or_else(|| Value::from(tag)),
+        "body": options.get("body").cloned().unwrap_or_else(|| Value::from("")),
+        "draft": options.get("draft").cloned().unwrap_or(Value::from(false)),
+        "prerelease": options.get("prerelease").cloned().unwrap_or(Value::from(false)),
+        "created_at": timestamp(),
+        "published_at": timestamp(),
+        "author": user,
+        "assets": []
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// The current time in ISO 8601 format, e.g. `2011-01-26T19:01:12Z`
+fn timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,165 @@
+//! Pluggable HTTP transports
+//!
+//! A `Github` client hands every fully prepared request to an `HttpTransport`
+//! and decodes whatever response it yields. `reqwest::Client` is the default
+//! implementation, but any type implementing this trait, such as an in-memory
+//! fake or a recording layer, may be provided to `Github::custom`.
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::{Request, Response};
+
+use crate::Future;
+
+/// A boxed transport, shared between clones of a `Github` client
+pub type BoxedTransport = Arc<dyn HttpTransport>;
+
+/// Sends HTTP requests on behalf of a `Github` client
+pub trait HttpTransport: fmt::Debug + Send + Sync {
+    /// Send a request, resolving to its response with a fully buffered body.
+    /// Non-2xx responses are not errors at this level
+    fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Bytes>>;
+}
+
+impl HttpTransport for reqwest::Client {
+    fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Bytes>> {
+        let client = self.clone();
+        Box::pin(async move {
+            let request = reqwest::Request::try_from(request)?;
+            let response = client.execute(request).await?;
+            let mut builder = Response::builder()
+                .status(response.status())
+                .version(response.version());
+            if let Some(headers) = builder.headers_mut() {
+                *headers = response.headers().clone();
+            }
+            let body = response.bytes().await?;
+            Ok(builder
+                .body(body)
+                .expect("response parts were taken from a valid response"))
+        })
+    }
+}
+
+impl<T> HttpTransport for Arc<T>
+where
+    T: HttpTransport + ?Sized,
+{
+    fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Bytes>> {
+        (**self).send(request)
+    }
+}
+
+/// A transport replaying canned responses, shared by the crate's unit tests
+#[cfg(test)]
+pub(crate) mod scripted {
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    use bytes::Bytes;
+    use futures::future;
+    use http::header::{HeaderName, HeaderValue};
+    use http::{Request, Response, StatusCode};
+
+    use super::HttpTransport;
+    use crate::{Credentials, Future, Github, GithubBuilder};
+
+    /// Replays its responses in order, recording the requests it's sent
+    #[derive(Debug, Default)]
+    pub(crate) struct Scripted {
+        pub(crate) responses: Mutex<Vec<Response<Bytes>>>,
+        pub(crate) requests: Mutex<Vec<Request<Vec<u8>>>>,
+    }
+
+    impl Scripted {
+        pub(crate) fn new(responses: Vec<(StatusCode, &'static str)>) -> Arc<Self> {
+            let responses = responses
+                .into_iter()
+                .map(|(status, body)| {
+                    Response::builder()
+                        .status(status)
+                        .body(Bytes::from_static(body.as_bytes()))
+                        .unwrap()
+                })
+                .collect();
+            Arc::new(Scripted {
+                responses: Mutex::new(responses),
+                ..Scripted::default()
+            })
+        }
+
+        /// Add a header to the `index`th response
+        pub(crate) fn header(
+            self: Arc<Self>,
+            index: usize,
+            name: &'static str,
+            value: &'static str,
+        ) -> Arc<Self> {
+            self.responses.lock().unwrap()[index].headers_mut().append(
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            );
+            self
+        }
+
+        /// Add a header to every response
+        pub(crate) fn header_all(
+            self: Arc<Self>,
+            name: &'static str,
+            value: &'static str,
+        ) -> Arc<Self> {
+            let count = self.responses.lock().unwrap().len();
+            (0..count).fold(self, |scripted, index| scripted.header(index, name, value))
+        }
+
+        pub(crate) fn requests(&self) -> MutexGuard<'_, Vec<Request<Vec<u8>>>> {
+            self.requests.lock().unwrap()
+        }
+
+        /// A client builder sending requests through this transport
+        pub(crate) fn builder(self: &Arc<Self>) -> GithubBuilder {
+            Github::builder("test-agent").transport(self.clone())
+        }
+
+        pub(crate) fn github(self: &Arc<Self>, credentials: Option<Credentials>) -> Github {
+            self.builder().credentials(credentials).build().unwrap()
+        }
+    }
+
+    impl HttpTransport for Scripted {
+        fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Bytes>> {
+            self.requests.lock().unwrap().push(request);
+            let response = self.responses.lock().unwrap().remove(0);
+            Box::pin(future::ok(response))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scripted::Scripted;
+    use crate::Credentials;
+    use http::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
+    use http::{Method, StatusCode};
+
+    #[tokio::test]
+    async fn custom_transport() {
+        let transport = Scripted::new(vec![(StatusCode::OK, r#"{"login": "octocat"}"#)]);
+        let github = transport.github(Some(Credentials::Token("secret".into())));
+
+        let user: serde_json::Value = github.get("/user").await.unwrap();
+        assert_eq!(user["login"], "octocat");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method(), Method::GET);
+        assert_eq!(requests[0].uri(), "https://api.github.com/user");
+        assert_eq!(requests[0].headers()[USER_AGENT], "test-agent");
+        assert_eq!(requests[0].headers()[AUTHORIZATION], "token secret");
+        assert_eq!(
+            requests[0].headers()[ACCEPT],
+            "application/vnd.github.v3+json"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 //! Users interface
+use crate::pagination::Page;
 use crate::{Future, Github, Stream};
 use serde::{Deserialize, Serialize};
 
@@ -130,4 +131,18 @@ impl Contributors {
         self.github
             .get_stream(&format!("/repos/{}/{}/contributors", self.owner, self.repo))
     }
+
+    /// provides a stream over each page of contributors
+    pub fn pages(&self) -> Stream<Page<User>> {
+        self.github
+            .get_page_stream(&format!("/repos/{}/{}/contributors", self.owner, self.repo))
+    }
+
+    /// fetch a single page of contributors, starting at page 1
+    pub fn page(&self, number: u32) -> Future<Page<User>> {
+        self.github.get_page(
+            &format!("/repos/{}/{}/contributors", self.owner, self.repo),
+            number,
+        )
+    }
 }
@@ -0,0 +1,289 @@
+//! Change polling
+//!
+//! `Github::watch` polls a resource with conditional requests, yielding its
+//! new value each time it changes. GitHub answers requests for unchanged
+//! resources with a `304 Not Modified`, which doesn't count against the rate
+//! limit. Polls are spaced by the `X-Poll-Interval` GitHub asks for, a minute
+//! until it does. `Github::watch_with` watches the result of any operation
+//! sending a single GET request, such as a service's `list`.
+//!
+//! Errors are yielded without ending the watch, which carries on polling
+//! until the stream is dropped. After a rate limit error it waits for the
+//! rate limit to reset before polling again. Not to be confused with
+//! `watching`, which manages repository subscriptions.
+//!
+//! ```no_run
+//! use futures::prelude::*;
+//! use hubcaps::issues::IssueListOptions;
+//! use hubcaps::{Credentials, Github};
+//! use serde_json::Value;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new(
+//!     "user-agent-name",
+//!     Credentials::Token("personal-access-token".into()),
+//! )?;
+//! let mut releases = github.watch::<Value>("/repos/softprops/hubcaps/releases/latest");
+//! while let Some(release) = releases.try_next().await? {
+//!     println!("latest release: {}", release["tag_name"]);
+//! }
+//!
+//! let options = IssueListOptions::builder().build();
+//! let issues = github
+//!     .watch_with(move |github| github.repo("softprops", "hubcaps").issues().list(&options))
+//!     .take(3)
+//!     .try_for_each(|issues| async move {
+//!         println!("{} open issues", issues.len());
+//!         Ok(())
+//!     });
+//! issues.await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::stream;
+use http::header::{HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use http::{Method, Request, StatusCode, Uri};
+
+use crate::middleware::Middleware;
+use crate::response::MetaSlot;
+use crate::{Error, ErrorKind, Future, Github, Result, Stream};
+
+/// How long to wait between polls until GitHub sends an `X-Poll-Interval`
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The state of a watch between polls
+struct Watcher<F> {
+    github: Github,
+    operation: F,
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    interval: Duration,
+    /// how long a rate limit asked to wait before the next poll
+    rate_limited: Option<Duration>,
+    polled: bool,
+}
+
+impl<F, T> Watcher<F>
+where
+    F: Fn(&Github) -> Future<T>,
+{
+    /// Run the operation, conditional on the validators of its last result.
+    /// Resolves to `None` if the result hasn't changed
+    async fn poll(&mut self) -> Result<Option<T>> {
+        let mut validators = Validators::default();
+        if let Some(etag) = &self.etag {
+            validators.headers.insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &self.last_modified {
+            validators
+                .headers
+                .insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        // outermost, so the validators are in place before anything else sees
+        // the request, as if the operation had sent them itself
+        let mut middleware = (*self.github.middleware).clone();
+        middleware.insert(0, Arc::new(validators));
+        let slot = MetaSlot::default();
+        let github = Github {
+            middleware: Arc::new(middleware),
+            response_meta: Some(slot.clone()),
+            ..self.github.clone()
+        };
+        let outcome = (self.operation)(&github).await;
+        self.rate_limited = outcome.as_ref().err().and_then(rate_limit_wait);
+        let meta = slot.lock().unwrap().take();
+        let meta = match meta {
+            Some(meta) => meta,
+            None => return outcome.map(Some),
+        };
+        if let Some(interval) = meta.poll_interval() {
+            self.interval = interval;
+        }
+        if meta.status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let value = outcome?;
+        let etag = meta.headers.get(ETAG).cloned();
+        let last_modified = meta.headers.get(LAST_MODIFIED).cloned();
+        // an `HttpCache` answers 304s with the response it has cached
+        let unchanged = (etag.is_some() || last_modified.is_some())
+            && etag == self.etag
+            && last_modified == self.last_modified;
+        self.etag = etag;
+        self.last_modified = last_modified;
+        Ok(if unchanged { None } else { Some(value) })
+    }
+
+    /// How long to wait before the next poll
+    fn delay(&mut self) -> Duration {
+        match self.rate_limited.take() {
+            Some(wait) => wait.max(self.interval),
+            None => self.interval,
+        }
+    }
+}
+
+/// Makes the watched request conditional. That's the first GET request the
+/// operation sends, and its retries, but not e.g. the requests for
+/// installation tokens made on the way
+#[derive(Debug, Default)]
+struct Validators {
+    headers: HeaderMap,
+    uri: Mutex<Option<Uri>>,
+}
+
+impl Middleware for Validators {
+    fn before_send(&self, request: &mut Request<Vec<u8>>) -> Result<()> {
+        if request.method() != Method::GET {
+            return Ok(());
+        }
+        let mut uri = self.uri.lock().unwrap();
+        if uri.get_or_insert_with(|| request.uri().clone()) == request.uri() {
+            for (name, value) in &self.headers {
+                request
+                    .headers_mut()
+                    .entry(name)
+                    .or_insert_with(|| value.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How long a rate limit error asks to wait before sending more requests
+fn rate_limit_wait(err: &Error) -> Option<Duration> {
+    match err.kind() {
+        ErrorKind::RateLimit { reset } => Some(*reset),
+        ErrorKind::SecondaryRateLimit { retry_after, .. } => Some(*retry_after),
+        _ => None,
+    }
+}
+
+/// See `Github::watch_with`
+pub(crate) fn watch<F, T>(github: Github, operation: F) -> Stream<T>
+where
+    F: Fn(&Github) -> Future<T> + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    let watcher = Watcher {
+        github,
+        operation,
+        etag: None,
+        last_modified: None,
+        interval: DEFAULT_POLL_INTERVAL,
+        rate_limited: None,
+        polled: false,
+    };
+    Box::pin(stream::unfold(watcher, |mut watcher| async move {
+        loop {
+            if watcher.polled {
+                tokio::time::sleep(watcher.delay()).await;
+            }
+            watcher.polled = true;
+            match watcher.poll().await {
+                Ok(None) => continue,
+                Ok(Some(value)) => return Some((Ok(value), watcher)),
+                Err(err) => return Some((Err(err), watcher)),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::scripted::Scripted;
+    use futures::future;
+    use futures::prelude::*;
+
+    #[tokio::test]
+    async fn watch() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, "[1]"),
+            (StatusCode::NOT_MODIFIED, ""),
+            (StatusCode::OK, "[1, 2]"),
+        ])
+        .header(0, "etag", "\"a\"")
+        .header(1, "etag", "\"a\"")
+        .header(2, "etag", "\"b\"")
+        .header_all("x-poll-interval", "0");
+        let github = transport.github(None);
+        let changes: Vec<Vec<u32>> = github
+            .watch("/repos/o/r/releases")
+            .take(2)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(changes, vec![vec![1], vec![1, 2]]);
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[0]
+            .headers()
+            .get(http::header::IF_NONE_MATCH)
+            .is_none());
+        for request in &requests[1..] {
+            assert_eq!(request.headers()[http::header::IF_NONE_MATCH], "\"a\"");
+        }
+    }
+
+    #[tokio::test]
+    async fn validators_only_apply_to_the_watched_request() {
+        let transport = Scripted::new(vec![
+            (StatusCode::OK, "{}"),
+            (StatusCode::OK, "[1]"),
+            (StatusCode::OK, "[2]"),
+        ])
+        .header_all("etag", "\"a\"");
+        let mut watcher = Watcher {
+            github: transport.github(None),
+            operation: |github: &Github| -> crate::Future<Vec<u32>> {
+                let github = github.clone();
+                Box::pin(async move {
+                    github.post::<serde_json::Value>("/things", vec![]).await?;
+                    let things = github.get("/things").await?;
+                    github.get::<Vec<u32>>("/other").await?;
+                    Ok(things)
+                })
+            },
+            etag: Some(HeaderValue::from_static("\"a\"")),
+            last_modified: None,
+            interval: DEFAULT_POLL_INTERVAL,
+            rate_limited: None,
+            polled: false,
+        };
+        assert_eq!(watcher.poll().await.unwrap(), None);
+
+        let requests = transport.requests();
+        assert!(requests[0].headers().get(IF_NONE_MATCH).is_none());
+        assert_eq!(requests[1].headers()[IF_NONE_MATCH], "\"a\"");
+        assert!(requests[2].headers().get(IF_NONE_MATCH).is_none());
+    }
+
+    #[tokio::test]
+    async fn waits_out_rate_limits() {
+        let mut watcher = Watcher {
+            github: Scripted::new(vec![]).github(None),
+            operation: |_: &Github| -> crate::Future<()> {
+                Box::pin(future::err(
+                    ErrorKind::SecondaryRateLimit {
+                        retry_after: Duration::from_secs(120),
+                        resource: None,
+                    }
+                    .into(),
+                ))
+            },
+            etag: None,
+            last_modified: None,
+            interval: DEFAULT_POLL_INTERVAL,
+            rate_limited: None,
+            polled: false,
+        };
+        assert!(watcher.poll().await.is_err());
+        assert_eq!(watcher.delay(), Duration::from_secs(120));
+        assert_eq!(watcher.delay(), DEFAULT_POLL_INTERVAL);
+    }
+}
@@ -2,6 +2,7 @@
 /// https://developer.github.com/v3/activity/watching
 use serde::Deserialize;
 
+use crate::pagination::Page;
 use crate::repositories::Repo;
 use crate::{Future, Github, Stream};
 
@@ -21,6 +22,17 @@ impl Watching {
         self.github.get_stream("/user/subscriptions")
     }
 
+    /// Provides a stream over each page of the repositories watched by the authenticated user.
+    pub fn pages(&self) -> Stream<Page<Repo>> {
+        self.github.get_page_stream("/user/subscriptions")
+    }
+
+    /// Fetch a single page of the repositories watched by the authenticated user, starting at
+    /// page 1.
+    pub fn page(&self, number: u32) -> Future<Page<Repo>> {
+        self.github.get_page("/user/subscriptions", number)
+    }
+
     /// https://developer.github.com/v3/activity/watching/#get-a-repository-subscription
     pub fn get_for_repo<O, R>(&self, owner: O, repo: R) -> Future<Subscription>
     where
@@ -0,0 +1,44 @@
+use hubcaps::blocking::Github;
+use hubcaps::issues::{IssueListOptions, IssueOptions};
+use hubcaps::testing::FakeGithub;
+use hubcaps::Result;
+
+#[test]
+fn blocking_client() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    let github = Github::from_async(fake.client())?;
+
+    let repo = github.repo("octocat", "hello-world");
+    assert_eq!(
+        repo.run(|repo| repo.get())?.full_name,
+        "octocat/hello-world"
+    );
+
+    let issues = repo.nested(|repo| repo.issues());
+    for title in &["first", "second", "third"] {
+        issues.run(|issues| {
+            issues.create(&IssueOptions::new(
+                *title,
+                None::<String>,
+                None::<String>,
+                None,
+                Vec::<String>::new(),
+            ))
+        })?;
+    }
+
+    let options = IssueListOptions::builder().per_page(2).build();
+    let titles = issues
+        .iter(|issues| issues.iter(&options))
+        .map(|issue| issue.map(|issue| issue.title))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(titles.len(), 3, "iterates past the first page");
+
+    let user = github
+        .raw()
+        .run(|raw| raw.get("/user").send::<serde_json::Value>())?;
+    assert_eq!(user["login"], "octocat");
+    assert!(github.rate_limit().run(|limit| limit.get()).is_ok());
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use futures::prelude::*;
+use hubcaps::comments::CommentOptions;
+use hubcaps::issues::{IssueListOptions, IssueOptions, State};
+use hubcaps::labels::LabelOptions;
+use hubcaps::pulls::{PullEditOptions, PullListOptions, PullOptions};
+use hubcaps::releases::ReleaseOptions;
+use hubcaps::repositories::{RepoOptions, UserRepoListOptions};
+use hubcaps::statuses::{self, StatusOptions};
+use hubcaps::testing::FakeGithub;
+use hubcaps::{Error, ErrorKind, Result};
+
+fn issue(title: &str, labels: Vec<&str>) -> IssueOptions {
+    IssueOptions::new(title, Some("body"), None::<String>, None, labels)
+}
+
+#[tokio::test]
+async fn repositories() -> Result<()> {
+    let fake = FakeGithub::new();
+    let github = fake.client();
+    let created = github
+        .repos()
+        .create(
+            &RepoOptions::builder("hello-world")
+                .description("hi")
+                .build(),
+        )
+        .await?;
+    assert_eq!(created.full_name, "octocat/hello-world");
+
+    let repo = github.repo("octocat", "hello-world").get().await?;
+    assert_eq!(repo.id, created.id);
+    assert_eq!(repo.description.as_deref(), Some("hi"));
+
+    let repos = github
+        .user_repos("octocat")
+        .list(&UserRepoListOptions::builder().build())
+        .await?;
+    assert_eq!(repos.len(), 1);
+
+    github.repo("octocat", "hello-world").delete().await?;
+    match github
+        .repo("octocat", "hello-world")
+        .get()
+        .await
+        .map_err(Error::into_kind)
+    {
+        Err(ErrorKind::Fault { code, .. }) => assert_eq!(code.as_u16(), 404),
+        otherwise => panic!("expected a 404, got {:?}", otherwise.map(|r| r.id)),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn issues_labels_and_comments() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    let repo = fake.client().repo("octocat", "hello-world");
+
+    repo.labels()
+        .create(&LabelOptions::new("bug", "d73a4a", "something is broken"))
+        .await?;
+    let first = repo.issues().create(&issue("first", vec!["bug"])).await?;
+    let second = repo.issues().create(&issue("second", vec!["new"])).await?;
+    assert_eq!((first.number, second.number), (1, 2));
+    assert_eq!(first.labels[0].color, "d73a4a");
+
+    let labels = repo.labels().list().await?;
+    assert_eq!(labels.len(), 2, "labels named on issues are created");
+
+    let issue = repo.issue(first.number);
+    issue.labels().add(vec!["new"]).await?;
+    issue.labels().remove("bug").await?;
+    let current: Vec<_> = issue
+        .labels()
+        .set(vec!["new", "triage"])
+        .await?
+        .into_iter()
+        .map(|label| label.name)
+        .collect();
+    assert_eq!(current, vec!["new", "triage"]);
+
+    issue
+        .comments()
+        .create(&CommentOptions {
+            body: "me too".into(),
+        })
+        .await?;
+    let comments = issue.comments().list(&Default::default()).await?;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].user.login, "octocat");
+
+    let edited = issue.edit(&self::issue("renamed", vec!["bug"])).await?;
+    assert_eq!(edited.title, "renamed");
+    assert_eq!(edited.labels.len(), 1);
+    let all = repo
+        .issues()
+        .list(&IssueListOptions::builder().state(State::All).build())
+        .await?;
+    assert_eq!(all.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn pulls() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    let repo = fake.client().repo("octocat", "hello-world");
+
+    repo.issues().create(&issue("an issue", vec![])).await?;
+    let pull = repo
+        .pulls()
+        .create(&PullOptions::new(
+            "a fix",
+            "fix",
+            "master",
+            Some("fixes #1"),
+        ))
+        .await?;
+    assert_eq!(pull.number, 2, "pulls share numbers with issues");
+    assert_eq!(pull.head.commit_ref, "fix");
+
+    let edited = repo
+        .pulls()
+        .get(pull.number)
+        .edit(&PullEditOptions::builder().state("closed").build())
+        .await?;
+    assert_eq!(edited.state, "closed");
+    assert!(edited.closed_at.is_some());
+
+    let open = repo
+        .pulls()
+        .list(&PullListOptions::builder().build())
+        .await?;
+    assert!(open.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn statuses() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    let repo = fake.client().repo("octocat", "hello-world");
+
+    let status = repo
+        .statuses()
+        .create(
+            "deadbeef",
+            &StatusOptions::builder(statuses::State::Success)
+                .context("ci")
+                .build(),
+        )
+        .await?;
+    assert_eq!(status.context, "ci");
+    let statuses = repo.statuses().list("deadbeef").await?;
+    assert_eq!(statuses.len(), 1);
+    assert!(repo.statuses().list("cafebabe").await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn releases() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    let repo = fake.client().repo("octocat", "hello-world");
+
+    let v1 = repo
+        .releases()
+        .create(&ReleaseOptions::builder("v1.0.0").build())
+        .await?;
+    repo.releases()
+        .create(
+            &ReleaseOptions::builder("v2.0.0-rc")
+                .prerelease(true)
+                .build(),
+        )
+        .await?;
+
+    assert_eq!(repo.releases().latest().await?.id, v1.id);
+    assert_eq!(
+        repo.releases().by_tag("v2.0.0-rc").await?.tag_name,
+        "v2.0.0-rc"
+    );
+    assert_eq!(repo.releases().list().await?.len(), 2);
+
+    repo.releases().delete(v1.id).await?;
+    assert!(repo.releases().latest().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn paginates_with_link_headers() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    let repo = fake.client().repo("octocat", "hello-world");
+    for n in 0..7 {
+        repo.issues()
+            .create(&issue(&format!("issue {}", n), vec![]))
+            .await?;
+    }
+
+    let options = IssueListOptions::builder().per_page(3).build();
+    let first_page = repo.issues().list(&options).await?;
+    assert_eq!(first_page.len(), 3);
+
+    let numbers: Vec<u64> = repo
+        .issues()
+        .iter(&options)
+        .map_ok(|issue| issue.number)
+        .try_collect()
+        .await?;
+    assert_eq!(numbers, vec![7, 6, 5, 4, 3, 2, 1]);
+
+    let pages = fake
+        .requests()
+        .into_iter()
+        .filter(|request| request.path.split(&['?', '&'][..]).any(|p| p == "page=3"))
+        .count();
+    assert_eq!(pages, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn paginates_concurrently() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    let mut github = fake.client();
+    github.set_page_concurrency(4);
+    let repo = github.repo("octocat", "hello-world");
+    for n in 0..10 {
+        repo.issues()
+            .create(&issue(&format!("issue {}", n), vec![]))
+            .await?;
+    }
+
+    let options = IssueListOptions::builder().per_page(2).build();
+    let numbers: Vec<u64> = repo
+        .issues()
+        .iter(&options)
+        .map_ok(|issue| issue.number)
+        .try_collect()
+        .await?;
+    assert_eq!(numbers, (1..=10).rev().collect::<Vec<_>>());
+
+    let mut pages: Vec<String> = fake
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "GET")
+        .filter_map(|request| {
+            request
+                .path
+                .split(&['?', '&'][..])
+                .find(|p| p.starts_with("page="))
+                .map(String::from)
+        })
+        .collect();
+    pages.sort();
+    assert_eq!(pages, vec!["page=2", "page=3", "page=4", "page=5"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn navigates_pages() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    let repo = fake.client().repo("octocat", "hello-world");
+    for n in 0..7 {
+        repo.issues()
+            .create(&issue(&format!("issue {}", n), vec![]))
+            .await?;
+    }
+    let options = IssueListOptions::builder().per_page(3).build();
+
+    let first = repo.issues().page(&options, 1).await?;
+    assert_eq!(first.number(), 1);
+    assert_eq!(first.total_pages(), Some(3));
+    assert_eq!(first.links.next_page(), Some(2));
+    assert!(first.prev().await?.is_none());
+
+    let last = first.last().await?.expect("last page");
+    assert_eq!(last.number(), 3);
+    assert_eq!(last.total_pages(), Some(3));
+    assert!(!last.has_next());
+    assert_eq!(last.items.len(), 1);
+
+    let second = last.goto(2).await?;
+    assert_eq!(second.links.prev_page(), Some(1));
+    let numbers: Vec<u64> = second.items.iter().map(|issue| issue.number).collect();
+    assert_eq!(numbers, vec![4, 3, 2]);
+
+    let pages: Vec<(u32, usize)> = repo
+        .issues()
+        .pages(&options)
+        .map_ok(|page| (page.number(), page.items.len()))
+        .try_collect()
+        .await?;
+    assert_eq!(pages, vec![(1, 3), (2, 3), (3, 1)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rate_limits() -> Result<()> {
+    let fake = FakeGithub::new();
+    fake.add_repo("octocat", "hello-world");
+    fake.set_rate_limit(60, 1);
+    let github = fake.client();
+
+    github.repo("octocat", "hello-world").get().await?;
+    let status = github.rate_limit().get().await?;
+    assert_eq!(status.resources.core.limit, 60);
+    assert_eq!(status.resources.core.remaining, 0);
+
+    match github
+        .repo("octocat", "hello-world")
+        .get()
+        .await
+        .map_err(Error::into_kind)
+    {
+        Err(ErrorKind::RateLimit { .. }) => (),
+        otherwise => panic!(
+            "expected a rate limit error, got {:?}",
+            otherwise.map(|r| r.id)
+        ),
+    }
+    Ok(())
+}